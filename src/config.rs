@@ -1,10 +1,14 @@
 use regex::Regex;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::env;
 use std::fs::File;
-use std::io::{self, BufRead, Error};
+use std::io::{self, BufRead};
 use std::path::{Path, PathBuf};
 
+use crate::error::Error;
+use crate::record::{Format, RelationPatterns};
+
 // This is the config data we'll pass into any functions that need it
 pub struct Config {
     pub record_path: PathBuf,
@@ -15,13 +19,91 @@ pub struct Config {
     pub template_string: String,
     pub template_references: HashMap<String, String>,
     pub default_status: String,
+    // The statuses a record can be given (statuses=Draft,Proposed,Approved), in the order they're
+    // normally reached.
+    pub statuses: Vec<String>,
+    // The statuses a record may move to from each status (transition.Draft=Proposed,Rejected). If
+    // none are given, a record may move between any of the statuses.
+    pub transitions: HashMap<String, Vec<String>>,
+    // Whether records carry a YAML front matter block (metadata=frontmatter) as well as the
+    // human-readable Status block.
+    pub front_matter: bool,
+    // Whether records use the MADR (Markdown Any Decision Records) layout (fileType=madr), which
+    // keeps the status in the front matter rather than in a Status block.
+    pub madr: bool,
+    // Whether new records are given a four digit number at the start of their file name
+    // (numbered=false turns this off, so records are named and found by their slug alone).
+    pub numbered: bool,
+    // Whether status changes record who made them, from `git config user.name` (actor=git).
+    pub record_actor: bool,
+    // The `.decisionrecords-config` file this was read from, if there is one.
+    pub config_file: Option<PathBuf>,
+    // The template file new records are made from, if one was found in the template directory.
+    pub template_source: Option<PathBuf>,
+    // The regular expressions which read the relations in each format, made when first needed
+    // (see `record::Relation::parse`).
+    pub(crate) relation_patterns: RefCell<HashMap<Format, RelationPatterns>>,
+}
+
+impl Config {
+    // Look up the translation of a string (e.g. "Status" or "Approved on DATE") in the language
+    // reference file, returning the string untouched if there's no translation for it.
+    pub fn translate(&self, needle_string: &str) -> String {
+        match self.template_references.get(needle_string) {
+            Some(value) => value.clone(),
+            None => needle_string.to_string(),
+        }
+    }
+
+    // The translated status line for a status, e.g. "Approved on DATE".
+    pub fn status_template(&self, status: &str) -> String {
+        self.translate(&format!("{} on DATE", status))
+    }
+
+    // The translated text before the date in a status line, e.g. "Approved on".
+    pub fn status_prefix(&self, status: &str) -> String {
+        let translated = self.status_template(status);
+        match translated.find("DATE") {
+            Some(position) => translated[..position].trim_end().to_string(),
+            None => translated,
+        }
+    }
+
+    // The status in the workflow with this name, ignoring case, e.g. "in review" is "In Review".
+    pub fn find_status(&self, name: &str) -> Option<&str> {
+        self.statuses
+            .iter()
+            .find(|status| status.eq_ignore_ascii_case(name.trim()))
+            .map(String::as_str)
+    }
+
+    // Whether a record may move from one status to another. Records with no status, or with a
+    // status the workflow doesn't know about, may move to any status.
+    pub fn allows_transition(&self, from: &str, to: &str) -> bool {
+        if self.transitions.is_empty() || from.is_empty() {
+            return true;
+        }
+        let known = self.find_status(from).is_some()
+            || self.transitions.contains_key(from)
+            || from == "Superseded"
+            || from == "Deprecated";
+        if !known {
+            return true;
+        }
+        self.transitions
+            .get(from)
+            .is_some_and(|allowed| allowed.iter().any(|status| status == to))
+    }
 }
 
 // This is the supervising function that will load the config and return it.
 pub fn load_config() -> Result<Config, Error> {
-    let current_dir = env::current_dir();
-    let config = find_and_read_configuration(Path::new(&current_dir.unwrap()))?;
-    return Ok(config);
+    load_config_from(&env::current_dir()?)
+}
+
+// Load the config, starting the search from this path rather than the current directory.
+pub fn load_config_from(path: &Path) -> Result<Config, Error> {
+    find_and_read_configuration(path)
 }
 
 // Some notes on this function:
@@ -36,9 +118,11 @@ pub fn load_config() -> Result<Config, Error> {
 // 3. A directory named `doc/decision_records` is found
 // 4. A file called `.decisionrecords-config` is found
 //
+// When both `.adr-dir` and `.decisionrecords-config` are in the same directory, the latter is used.
+//
 // Indicators 1, 2 and 3 offer no customization, while indicator 4 allows you to specify particular
 // templates, configuration defaults and file formats.
-fn find_and_read_configuration(path: &Path) -> Result<Config, io::Error> {
+fn find_and_read_configuration(path: &Path) -> Result<Config, Error> {
     // Define the default config to use
     let mut config = Config {
         record_path: path.to_path_buf(),
@@ -49,6 +133,19 @@ fn find_and_read_configuration(path: &Path) -> Result<Config, io::Error> {
         template_string: String::from("# NUMBER. TITLE\u{000A}\u{000A}Date: DATE\u{000A}\u{000A}## Status\u{000A}\u{000A}STATUS\u{000A}\u{000A}## Context\u{000A}\u{000A}This is the context.\u{000A}\u{000A}## Decision\u{000A}\u{000A}This is the decision that was made.\u{000A}\u{000A}## Consequence\u{000A}\u{000A}This is the consequence of the decision.\u{000A}"),
         template_references: HashMap::new(),
         default_status: "Approved".to_string(),
+        statuses: vec![
+            String::from("Proposed"),
+            String::from("Approved"),
+            String::from("Rejected"),
+        ],
+        transitions: HashMap::new(),
+        front_matter: false,
+        madr: false,
+        numbered: true,
+        record_actor: false,
+        config_file: None,
+        template_source: None,
+        relation_patterns: RefCell::new(HashMap::new()),
     };
 
     // Create new variables
@@ -65,6 +162,12 @@ fn find_and_read_configuration(path: &Path) -> Result<Config, io::Error> {
     let re_template = Regex::new(r"^template=(.*)$").unwrap();
     let re_filetype = Regex::new(r"^fileType=(.*)$").unwrap();
     let re_default_proposed = Regex::new(r"defaultProposed=(.*)$").unwrap();
+    let re_metadata = Regex::new(r"^metadata=(.*)$").unwrap();
+    let re_numbered = Regex::new(r"^numbered=(.*)$").unwrap();
+    let re_statuses = Regex::new(r"^statuses=(.*)$").unwrap();
+    let re_actor = Regex::new(r"^actor=(.*)$").unwrap();
+    let re_transition = Regex::new(r"^transition\.(.+?)=(.*)$").unwrap();
+    let re_default_status = Regex::new(r"^defaultStatus=(.*)$").unwrap();
     let re_short_language = Regex::new("^([a-zA-Z]+)([-_][a-zA-Z]+|)$").unwrap();
     let re_reference_construct = Regex::new("^(.*)=\"(.*)\"").unwrap();
 
@@ -73,10 +176,11 @@ fn find_and_read_configuration(path: &Path) -> Result<Config, io::Error> {
 
     // This is where the tree walking starts
     while pathbuf.exists() {
-        // Looking first of all for the indicator `.adr-dir`.
+        // Looking first of all for the indicator `.adr-dir`, unless there's also a
+        // `.decisionrecords-config` here (e.g. after an import), which is read instead.
         pathbuf.push(".adr-dir");
 
-        if pathbuf.exists() {
+        if pathbuf.exists() && !pathbuf.with_file_name(".decisionrecords-config").exists() {
             // Where is our root path?
             let mut root_path: PathBuf = pathbuf.clone();
             // Set the "root path" to be the directory that the config file exists in.
@@ -86,21 +190,24 @@ fn find_and_read_configuration(path: &Path) -> Result<Config, io::Error> {
 
             // This will be the relative path that the file directs us to.
             let mut str_doc_path: String = String::from("");
-            // It may occur that, outside of "spec", the file has several lines. Panic if this happens.
+            // It may occur that, outside of "spec", the file has several lines. Refuse to guess if this happens.
             let mut this_file_has_multiple_lines: bool = false;
             // Read the lines in the file
             if let Ok(lines) = get_lines_from_a_file(&pathbuf) {
-                for line in lines {
-                    if let Ok(line) = line {
-                        // Only read lines which have content in them!
-                        if line.chars().count() > 0 {
-                            // If we've read one line already, and another appears, this is a fault.
-                            if this_file_has_multiple_lines {
-                                panic!(".adr-dir contains multiple lines which is against spec.");
-                            } else {
-                                str_doc_path = line;
-                                this_file_has_multiple_lines = true;
-                            }
+                for line in lines.map_while(Result::ok) {
+                    // Only read lines which have content in them!
+                    if line.chars().count() > 0 {
+                        // If we've read one line already, and another appears, this is a fault.
+                        if this_file_has_multiple_lines {
+                            return Err(Error::ConfigParse {
+                                path: pathbuf,
+                                message: String::from(
+                                    "contains multiple lines, which is against the adr-tools spec",
+                                ),
+                            });
+                        } else {
+                            str_doc_path = line;
+                            this_file_has_multiple_lines = true;
                         }
                     }
                 }
@@ -124,7 +231,7 @@ fn find_and_read_configuration(path: &Path) -> Result<Config, io::Error> {
                     }
                 }
                 // Turn the path object into a PathBuf which the config object requires.
-                config.record_path = PathBuf::from(record_path);
+                config.record_path = record_path;
             }
             // Early exit - we've got our config!
             return Ok(config);
@@ -137,6 +244,7 @@ fn find_and_read_configuration(path: &Path) -> Result<Config, io::Error> {
         if pathbuf.exists() {
             // Have we found a template directory?
             let mut def_template_dir: bool = false;
+            config.config_file = Some(pathbuf.clone());
             // Where is our root path?
             let mut root_path: PathBuf = pathbuf.clone();
             // Set the "root path" to be the directory that the config file exists in.
@@ -144,71 +252,101 @@ fn find_and_read_configuration(path: &Path) -> Result<Config, io::Error> {
 
             // Read the config file and loop through each line
             if let Ok(lines) = get_lines_from_a_file(pathbuf) {
-                for line in lines {
-                    if let Ok(line) = line {
-                        // This line is not empty
-                        if line.chars().count() > 0 {
-                            // The record path is relative to the root directory (records=<dir>). If it's defined, use it.
-                            if re_record_path.is_match(&line) {
-                                // Create a pathbuf for the record directory, based on the relative record directory
-                                let mut record_path: PathBuf = root_path.clone();
-
-                                // Parse the config value
-                                let str_doc_path = re_record_path.replace(&line, "$1");
-
-                                // So that we get a consistent handling of "Unix" style and "Windows" style
-                                // paths, check for the regex using either \ or / directory terminators... and
-                                // split on that. Push each directory terminator into the record path variable.
-                                if unix_path.is_match(&str_doc_path) {
-                                    let split_path_array = str_doc_path.split("/");
+                for line in lines.map_while(Result::ok) {
+                    // This line is not empty
+                    if line.chars().count() > 0 {
+                        // The record path is relative to the root directory (records=<dir>). If it's defined, use it.
+                        if re_record_path.is_match(&line) {
+                            // Create a pathbuf for the record directory, based on the relative record directory
+                            let mut record_path: PathBuf = root_path.clone();
+
+                            // Parse the config value
+                            let str_doc_path = re_record_path.replace(&line, "$1");
+
+                            // So that we get a consistent handling of "Unix" style and "Windows" style
+                            // paths, check for the regex using either \ or / directory terminators... and
+                            // split on that. Push each directory terminator into the record path variable.
+                            if unix_path.is_match(&str_doc_path) {
+                                let split_path_array = str_doc_path.split("/");
+                                for split_path in split_path_array {
+                                    record_path.push(split_path);
+                                }
+                            } else {
+                                if windows_path.is_match(&str_doc_path) {
+                                    let split_path_array = str_doc_path.split(r"\\");
                                     for split_path in split_path_array {
                                         record_path.push(split_path);
                                     }
                                 } else {
-                                    if windows_path.is_match(&str_doc_path) {
-                                        let split_path_array = str_doc_path.split(r"\\");
-                                        for split_path in split_path_array {
-                                            record_path.push(split_path);
-                                        }
-                                    } else {
-                                        record_path.push(String::from(str_doc_path));
-                                    }
+                                    record_path.push(String::from(str_doc_path));
                                 }
-                                // And store it
-                                config.record_path = record_path;
-                            }
-                            // If a language is defined (language=<code>), store it.
-                            if re_language.is_match(&line) {
-                                let language = re_language.replace(&line, "$1");
-                                config.template_language = String::from(language);
-                            }
-                            // If a template directory is specified (templateDir=<dir>), use it.
-                            if re_template_dir.is_match(&line) {
-                                let template_dir = re_template_dir.replace(&line, "$1");
-                                let mut template_path: PathBuf = root_path.clone();
-                                template_path.push(String::from(template_dir));
-                                config.template_path = template_path;
-                                def_template_dir = true;
-                            }
-                            // If the name of the template file to use is defined (template=<file_prefix>), use it.
-                            if re_template.is_match(&line) {
-                                let template = re_template.replace(&line, "$1");
-                                config.template_file = String::from(template);
                             }
-                            // If the file type to use is defined (format=<suffix>), use it. As we do basic string conversion, suggest md and rst are the only two used.
-                            if re_filetype.is_match(&line) {
-                                let filetype = re_filetype.replace(&line, "$1");
+                            // And store it
+                            config.record_path = record_path;
+                        }
+                        // If a language is defined (language=<code>), store it.
+                        if re_language.is_match(&line) {
+                            let language = re_language.replace(&line, "$1");
+                            config.template_language = String::from(language);
+                        }
+                        // If a template directory is specified (templateDir=<dir>), use it.
+                        if re_template_dir.is_match(&line) {
+                            let template_dir = re_template_dir.replace(&line, "$1");
+                            let mut template_path: PathBuf = root_path.clone();
+                            template_path.push(String::from(template_dir));
+                            config.template_path = template_path;
+                            def_template_dir = true;
+                        }
+                        // If the name of the template file to use is defined (template=<file_prefix>), use it.
+                        if re_template.is_match(&line) {
+                            let template = re_template.replace(&line, "$1");
+                            config.template_file = String::from(template);
+                        }
+                        // If the file type to use is defined (format=<suffix>), use it. As we do basic string conversion, suggest md and rst are the only two used.
+                        if re_filetype.is_match(&line) {
+                            let filetype = re_filetype.replace(&line, "$1");
+                            // MADR records are Markdown files with a different layout.
+                            if filetype == "madr" {
+                                config.madr = true;
+                                config.template_format = String::from("md");
+                            } else {
                                 config.template_format = String::from(filetype);
                             }
-                            // If the value to store DRs as "proposed" by default is defined (defaultProposed=<bool>), translate and store it.
-                            if re_default_proposed.is_match(&line) {
-                                let default_proposed: String =
-                                    re_default_proposed.replace(&line, "$1").parse().unwrap();
-                                if default_proposed == "true" {
-                                    config.default_status = "Proposed".to_string();
-                                }
+                        }
+                        // If the value to store DRs as "proposed" by default is defined (defaultProposed=<bool>), translate and store it.
+                        if re_default_proposed.is_match(&line) {
+                            let default_proposed: String =
+                                re_default_proposed.replace(&line, "$1").to_string();
+                            if default_proposed == "true" {
+                                config.default_status = "Proposed".to_string();
                             }
                         }
+                        // If the records should carry YAML front matter (metadata=frontmatter), note it.
+                        if let Some(captures) = re_metadata.captures(&line) {
+                            config.front_matter = captures[1].trim() == "frontmatter";
+                        }
+                        // If records should be named by their slug alone (numbered=false), note it.
+                        if let Some(captures) = re_numbered.captures(&line) {
+                            config.numbered = captures[1].trim() != "false";
+                        }
+                        // If status changes should say who made them (actor=git), note it.
+                        if let Some(captures) = re_actor.captures(&line) {
+                            config.record_actor = captures[1].trim() == "git";
+                        }
+                        // If the statuses of the workflow are listed (statuses=<a>,<b>), use them.
+                        if let Some(captures) = re_statuses.captures(&line) {
+                            config.statuses = split_list(&captures[1]);
+                        }
+                        // If the moves from a status are listed (transition.<from>=<a>,<b>), store them.
+                        if let Some(captures) = re_transition.captures(&line) {
+                            config
+                                .transitions
+                                .insert(captures[1].trim().to_string(), split_list(&captures[2]));
+                        }
+                        // If the status of new records is given (defaultStatus=<status>), use it.
+                        if let Some(captures) = re_default_status.captures(&line) {
+                            config.default_status = captures[1].trim().to_string();
+                        }
                     }
                 }
             }
@@ -238,22 +376,22 @@ fn find_and_read_configuration(path: &Path) -> Result<Config, io::Error> {
                 let mut default_template_reference_filename: String = config.template_file.clone();
 
                 // Amend the relevant suffixes
-                long_template_filename.push_str(".");
+                long_template_filename.push('.');
                 long_template_filename.push_str(&String::from(&config.template_language));
-                long_template_filename.push_str(".");
+                long_template_filename.push('.');
                 long_template_filename.push_str(&String::from(&config.template_format));
-                long_template_reference_filename.push_str(".");
+                long_template_reference_filename.push('.');
                 long_template_reference_filename.push_str(&String::from(&config.template_language));
                 long_template_reference_filename.push_str(".ref");
-                short_template_filename.push_str(".");
-                short_template_filename.push_str(&String::from(short_language));
-                short_template_filename.push_str(".");
+                short_template_filename.push('.');
+                short_template_filename.push_str(&short_language);
+                short_template_filename.push('.');
                 short_template_filename.push_str(&String::from(&config.template_format));
-                short_template_reference_filename.push_str(".");
+                short_template_reference_filename.push('.');
                 short_template_reference_filename
                     .push_str(&String::from(&config.template_language));
                 short_template_reference_filename.push_str(".ref");
-                default_template_filename.push_str(".");
+                default_template_filename.push('.');
                 default_template_filename.push_str(&String::from(&config.template_format));
                 default_template_reference_filename.push_str(".ref");
 
@@ -267,33 +405,30 @@ fn find_and_read_configuration(path: &Path) -> Result<Config, io::Error> {
 
                 // Then look to see if those files exist, and if so, read them into the config
                 if long_template_file.exists() {
-                    if let Ok(lines) = get_lines_from_a_file(long_template_file) {
+                    if let Ok(lines) = get_lines_from_a_file(&long_template_file) {
+                        config.template_source = Some(long_template_file.clone());
                         config.template_string = String::from("");
-                        for line in lines {
-                            if let Ok(line) = line {
-                                config.template_string.push_str(&line);
-                                config.template_string.push_str("\u{000A}");
-                            }
+                        for line in lines.map_while(Result::ok) {
+                            config.template_string.push_str(&line);
+                            config.template_string.push('\u{000A}');
                         }
                     }
                 } else if short_template_file.exists() {
-                    if let Ok(lines) = get_lines_from_a_file(short_template_file) {
+                    if let Ok(lines) = get_lines_from_a_file(&short_template_file) {
+                        config.template_source = Some(short_template_file.clone());
                         config.template_string = String::from("");
-                        for line in lines {
-                            if let Ok(line) = line {
-                                config.template_string.push_str(&line);
-                                config.template_string.push_str("\u{000A}");
-                            }
+                        for line in lines.map_while(Result::ok) {
+                            config.template_string.push_str(&line);
+                            config.template_string.push('\u{000A}');
                         }
                     }
                 } else if default_template_file.exists() {
-                    if let Ok(lines) = get_lines_from_a_file(default_template_file) {
+                    if let Ok(lines) = get_lines_from_a_file(&default_template_file) {
+                        config.template_source = Some(default_template_file.clone());
                         config.template_string = String::from("");
-                        for line in lines {
-                            if let Ok(line) = line {
-                                config.template_string.push_str(&line);
-                                config.template_string.push_str("\u{000A}");
-                            }
+                        for line in lines.map_while(Result::ok) {
+                            config.template_string.push_str(&line);
+                            config.template_string.push('\u{000A}');
                         }
                     }
                 }
@@ -302,46 +437,40 @@ fn find_and_read_configuration(path: &Path) -> Result<Config, io::Error> {
                 // and write those key/value pairs into the config
                 if default_template_reference_file.exists() {
                     if let Ok(lines) = get_lines_from_a_file(default_template_reference_file) {
-                        for line in lines {
-                            if let Ok(line) = line {
-                                let key = re_reference_construct
-                                    .replace(&String::from(&line), "$1")
-                                    .to_string();
-                                let value = re_reference_construct
-                                    .replace(&String::from(&line), "$2")
-                                    .to_string();
-                                config.template_references.insert(key, value);
-                            }
+                        for line in lines.map_while(Result::ok) {
+                            let key = re_reference_construct
+                                .replace(&String::from(&line), "$1")
+                                .to_string();
+                            let value = re_reference_construct
+                                .replace(&String::from(&line), "$2")
+                                .to_string();
+                            config.template_references.insert(key, value);
                         }
                     }
                 }
                 if short_template_reference_file.exists() {
                     if let Ok(lines) = get_lines_from_a_file(short_template_reference_file) {
-                        for line in lines {
-                            if let Ok(line) = line {
-                                let key = re_reference_construct
-                                    .replace(&String::from(&line), "$1")
-                                    .to_string();
-                                let value = re_reference_construct
-                                    .replace(&String::from(&line), "$2")
-                                    .to_string();
-                                config.template_references.insert(key, value);
-                            }
+                        for line in lines.map_while(Result::ok) {
+                            let key = re_reference_construct
+                                .replace(&String::from(&line), "$1")
+                                .to_string();
+                            let value = re_reference_construct
+                                .replace(&String::from(&line), "$2")
+                                .to_string();
+                            config.template_references.insert(key, value);
                         }
                     }
                 }
                 if long_template_reference_file.exists() {
                     if let Ok(lines) = get_lines_from_a_file(long_template_reference_file) {
-                        for line in lines {
-                            if let Ok(line) = line {
-                                let key = re_reference_construct
-                                    .replace(&String::from(&line), "$1")
-                                    .to_string();
-                                let value = re_reference_construct
-                                    .replace(&String::from(&line), "$2")
-                                    .to_string();
-                                config.template_references.insert(key, value);
-                            }
+                        for line in lines.map_while(Result::ok) {
+                            let key = re_reference_construct
+                                .replace(&String::from(&line), "$1")
+                                .to_string();
+                            let value = re_reference_construct
+                                .replace(&String::from(&line), "$2")
+                                .to_string();
+                            config.template_references.insert(key, value);
                         }
                     }
                 }
@@ -379,17 +508,25 @@ fn find_and_read_configuration(path: &Path) -> Result<Config, io::Error> {
         pathbuf.pop();
 
         // Check and see if the new directory ends with a slash, and that we've been here (because of how lookups work) twice
-        if path_regex.is_match(&pathbuf.display().to_string()) {
-            if pathbuf.display().to_string() == lastpath {
-                panic!("Decision Record path not found");
-            }
+        if path_regex.is_match(&pathbuf.display().to_string())
+            && pathbuf.display().to_string() == lastpath
+        {
+            return Err(Error::NotInitialised(path.to_path_buf()));
         }
 
         // Update the path we're in for the next loop round and go again.
         lastpath = pathbuf.display().to_string();
     }
-    // We've somehow failed if we got here, so say so!
-    panic!("Path not found!")
+    // We've walked off the top of the filesystem (or started somewhere which doesn't exist), so say so!
+    Err(Error::NotInitialised(path.to_path_buf()))
+}
+
+// Split a comma separated list from the config, dropping any empty items.
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
 }
 
 // Based on https://doc.rust-lang.org/rust-by-example/std_misc/file/read_lines.html
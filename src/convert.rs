@@ -0,0 +1,480 @@
+// Convert records between Markdown and Restructured Text. The title, the headings and the Status
+// block are written by the record itself in its new format, so only the text under each heading
+// needs translating here: links, inline code, lists, code blocks, comments and sub-headings.
+
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::markup::{self, indent_of, Replacement};
+use crate::record::{Format, Record, Section};
+
+// The characters used to underline the headings inside a Restructured Text section, from the
+// highest level down.
+const UNDERLINES: [char; 4] = ['=', '-', '~', '^'];
+
+// The record, rewritten in the `to` format and renamed to match. `targets` holds the file names
+// (relative to the record directory) of every record being converted, with their new names, so
+// that links between them are pointed at the new files.
+pub fn record(record: &Record, to: Format, targets: &HashMap<String, String>) -> Record {
+    let from = record.format;
+    let mut converted = record.with_format(to, |lines| convert_lines(lines, from, to, targets));
+    for relation in &mut converted.relations {
+        if let Some(target) = targets.get(&relation.target) {
+            relation.target = target.clone();
+        }
+    }
+    let status_heading = converted.status_heading().to_string();
+    for section in &mut converted.sections {
+        if section.heading != status_heading {
+            section.heading = convert_inline(&section.heading, from, to, targets);
+        }
+    }
+    // Restructured Text records only have one level of heading between the rules of `*`, so the
+    // headings below that become underlined headings inside the section above them.
+    if to == Format::ReStructuredText {
+        converted.sections = fold_subheadings(std::mem::take(&mut converted.sections));
+    }
+    converted
+}
+
+// Translate some lines of text from one format to the other. Only Markdown and Restructured Text
+// are translated; anything else is returned as it was.
+pub fn convert_lines(
+    lines: &[String],
+    from: Format,
+    to: Format,
+    targets: &HashMap<String, String>,
+) -> Vec<String> {
+    match (from, to) {
+        (Format::Markdown, Format::ReStructuredText) => markdown_to_rst(lines, targets),
+        (Format::ReStructuredText, Format::Markdown) => rst_to_markdown(lines, targets),
+        _ => lines.to_vec(),
+    }
+}
+
+fn markdown_to_rst(lines: &[String], targets: &HashMap<String, String>) -> Vec<String> {
+    let from = Format::Markdown;
+    let to = Format::ReStructuredText;
+
+    let mut output: Vec<String> = Vec::new();
+    // The list items the current line is inside, as the Markdown indent of each, with the indent
+    // of the item and of its text in Restructured Text.
+    let mut lists: Vec<(usize, usize, usize)> = Vec::new();
+
+    let mut index = 0;
+    while index < lines.len() {
+        let line = &lines[index];
+        let trimmed = line.trim();
+
+        // Fenced code blocks become a code-block directive, or a literal block without a language.
+        if let Some(captures) = markup::RE_FENCE.captures(line) {
+            let indent = captures[1].to_string();
+            let fence = captures[2].to_string();
+            push_blank(&mut output);
+            if captures[3].is_empty() {
+                output.push(format!("{}::", indent));
+            } else {
+                output.push(format!("{}.. code-block:: {}", indent, &captures[3]));
+            }
+            output.push(String::new());
+            index += 1;
+            while index < lines.len() && !lines[index].trim_start().starts_with(&fence) {
+                let code = lines[index].strip_prefix(&indent).unwrap_or(&lines[index]);
+                if code.trim().is_empty() {
+                    output.push(String::new());
+                } else {
+                    output.push(format!("{}   {}", indent, code));
+                }
+                index += 1;
+            }
+            index += 1;
+            if lines.get(index).is_some_and(|next| !next.trim().is_empty()) {
+                output.push(String::new());
+            }
+            continue;
+        }
+
+        // HTML comments become Restructured Text comments, with any further lines indented.
+        if trimmed.starts_with("<!--") {
+            let mut comment: Vec<String> = Vec::new();
+            loop {
+                comment.push(lines[index].trim().to_string());
+                if lines[index].contains("-->") || index + 1 >= lines.len() {
+                    break;
+                }
+                index += 1;
+            }
+            let text = comment.join("\u{000A}");
+            let text = text
+                .trim_start_matches("<!--")
+                .trim_end_matches("-->")
+                .trim();
+            let mut comment_lines = text.lines();
+            match comment_lines.next() {
+                Some(first) => output.push(format!(".. {}", first.trim())),
+                None => output.push(String::from("..")),
+            }
+            for rest in comment_lines {
+                output.push(format!("   {}", rest.trim()));
+            }
+            index += 1;
+            continue;
+        }
+
+        if trimmed.is_empty() {
+            output.push(String::new());
+            index += 1;
+            continue;
+        }
+
+        // List items. Restructured Text wants a nested list to be indented to the text of the
+        // item above it, with a blank line before and after it.
+        if let Some(captures) = markup::RE_MARKDOWN_ITEM.captures(line) {
+            let indent = captures[1].chars().count();
+            let marker = if captures[2].starts_with(|c: char| c.is_ascii_digit()) {
+                captures[2].replace(')', ".")
+            } else {
+                String::from("-")
+            };
+            let depth = lists.len();
+            while lists.last().is_some_and(|(outer, _, _)| *outer > indent) {
+                lists.pop();
+            }
+            if lists.len() < depth {
+                push_blank(&mut output);
+            }
+            let rst_indent = match lists.last() {
+                Some((outer, rst_indent, _)) if *outer == indent => {
+                    let rst_indent = *rst_indent;
+                    lists.pop();
+                    rst_indent
+                }
+                Some((_, _, text_indent)) => {
+                    push_blank(&mut output);
+                    *text_indent
+                }
+                None => 0,
+            };
+            lists.push((indent, rst_indent, rst_indent + marker.len() + 1));
+            output.push(format!(
+                "{}{} {}",
+                " ".repeat(rst_indent),
+                marker,
+                convert_inline(&captures[3], from, to, targets)
+            ));
+            index += 1;
+            continue;
+        }
+
+        // The rest of a list item, lined up with its text.
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, _, text_indent)) = lists.last() {
+                output.push(format!(
+                    "{}{}",
+                    " ".repeat(*text_indent),
+                    convert_inline(trimmed, from, to, targets)
+                ));
+                index += 1;
+                continue;
+            }
+        } else if !lists.is_empty() {
+            if output.last().is_some_and(|last| !last.is_empty()) {
+                // Lazy continuation of the last list item.
+                if let Some((_, _, text_indent)) = lists.last() {
+                    output.push(format!(
+                        "{}{}",
+                        " ".repeat(*text_indent),
+                        convert_inline(trimmed, from, to, targets)
+                    ));
+                    index += 1;
+                    continue;
+                }
+            }
+            lists.clear();
+        }
+
+        output.push(convert_inline(line, from, to, targets));
+        index += 1;
+    }
+    output
+}
+
+fn rst_to_markdown(lines: &[String], targets: &HashMap<String, String>) -> Vec<String> {
+    let from = Format::ReStructuredText;
+    let to = Format::Markdown;
+
+    let mut output: Vec<String> = Vec::new();
+    // The underline characters in the order they were first seen, which sets their level.
+    let mut underlines: Vec<char> = Vec::new();
+    // A paragraph ending in "::" is followed by a literal block.
+    let mut literal_next = false;
+
+    let mut index = 0;
+    while index < lines.len() {
+        let line = &lines[index];
+        let trimmed = line.trim();
+
+        let directive = markup::RE_CODE_DIRECTIVE.captures(line);
+        let starts_literal = literal_next
+            && line.starts_with([' ', '\t'])
+            && output.last().is_some_and(|last| last.is_empty());
+        if directive.is_some() || starts_literal {
+            let (indent, language) = match &directive {
+                Some(captures) => (captures[1].to_string(), captures[2].to_string()),
+                None => (String::new(), String::new()),
+            };
+            literal_next = false;
+            let (code, trailing_blank) = markup::literal_block(
+                lines,
+                &mut index,
+                indent.chars().count(),
+                directive.is_some(),
+            );
+            push_blank(&mut output);
+            output.push(format!("{}```{}", indent, language));
+            for code_line in code {
+                if code_line.is_empty() {
+                    output.push(String::new());
+                } else {
+                    output.push(format!("{}{}", indent, code_line));
+                }
+            }
+            output.push(format!("{}```", indent));
+            if trailing_blank {
+                output.push(String::new());
+            }
+            continue;
+        }
+
+        if trimmed.is_empty() {
+            output.push(String::new());
+            index += 1;
+            continue;
+        }
+
+        // Comments, but not directives (".. note::"), link targets (".. _name:") or footnotes.
+        if let Some(captures) = markup::RE_RST_COMMENT.captures(line) {
+            let text = captures.get(2).map_or("", |text| text.as_str());
+            if !text.contains("::") && !text.starts_with(['_', '[', '|']) {
+                let indent = captures[1].to_string();
+                let mut comment: Vec<String> = Vec::new();
+                if !text.trim().is_empty() {
+                    comment.push(text.trim().to_string());
+                }
+                index += 1;
+                while index < lines.len()
+                    && !lines[index].trim().is_empty()
+                    && indent_of(&lines[index]) > indent.chars().count()
+                {
+                    comment.push(lines[index].trim().to_string());
+                    index += 1;
+                }
+                if comment.len() > 1 {
+                    output.push(format!("{}<!--", indent));
+                    for comment_line in comment {
+                        output.push(format!("{}{}", indent, comment_line));
+                    }
+                    output.push(format!("{}-->", indent));
+                } else {
+                    output.push(format!("{}<!-- {} -->", indent, comment.join("")));
+                }
+                continue;
+            }
+        }
+
+        // Headings inside the section, underlined with punctuation.
+        if !line.starts_with([' ', '\t'])
+            && !markup::RE_RST_ITEM.is_match(line)
+            && output.last().is_none_or(|last| last.is_empty())
+            && index + 1 < lines.len()
+            && markup::RE_UNDERLINE.is_match(lines[index + 1].trim())
+            && lines[index + 1].trim().chars().count() >= trimmed.chars().count()
+        {
+            let underline = lines[index + 1].trim().chars().next().unwrap_or('=');
+            let level = match underlines.iter().position(|c| *c == underline) {
+                Some(level) => level,
+                None => {
+                    underlines.push(underline);
+                    underlines.len() - 1
+                }
+            };
+            output.push(format!(
+                "{} {}",
+                "#".repeat(level + 3),
+                convert_inline(trimmed, from, to, targets)
+            ));
+            literal_next = false;
+            index += 2;
+            continue;
+        }
+
+        // "Like this::" reads as "Like this:", and a lone "::" disappears.
+        let (starts_literal, text) = markup::literal_marker(line);
+        literal_next = starts_literal;
+        if text.is_empty() {
+            index += 1;
+            continue;
+        }
+
+        match markup::RE_RST_ITEM.captures(text) {
+            Some(captures) => {
+                let marker = if &captures[2] == "#." {
+                    "1."
+                } else if captures[2].starts_with(|c: char| c.is_ascii_digit()) {
+                    &captures[2]
+                } else {
+                    "*"
+                };
+                output.push(format!(
+                    "{}{} {}",
+                    &captures[1],
+                    marker,
+                    convert_inline(&captures[3], from, to, targets)
+                ));
+            }
+            None => output.push(convert_inline(text, from, to, targets)),
+        }
+        index += 1;
+    }
+    output
+}
+
+// Translate the inline markup in a line: code, links and emphasis. Links to other records are
+// written in the same way as the links in the Status block.
+fn convert_inline(
+    text: &str,
+    from: Format,
+    to: Format,
+    targets: &HashMap<String, String>,
+) -> String {
+    let replacements: Vec<(&Regex, Replacement)> = match (from, to) {
+        (Format::Markdown, Format::ReStructuredText) => vec![
+            (
+                &markup::RE_CODE,
+                Box::new(|captures: &Captures| format!("``{}``", &captures[1])),
+            ),
+            (
+                &markup::RE_MARKDOWN_LINK,
+                Box::new(|captures: &Captures| {
+                    // Images are left alone, as they're a directive of their own.
+                    if !captures[1].is_empty() {
+                        return captures[0].to_string();
+                    }
+                    let label = if captures[2].is_empty() {
+                        &captures[3]
+                    } else {
+                        &captures[2]
+                    };
+                    match local_target(&captures[3], targets) {
+                        Some(target) if target.ends_with(".rst") => to.link(label, &target),
+                        _ => format!("`{} <{}>`_", label, &captures[3]),
+                    }
+                }),
+            ),
+            (
+                &markup::RE_AUTOLINK,
+                Box::new(|captures: &Captures| captures[1].to_string()),
+            ),
+        ],
+        (Format::ReStructuredText, Format::Markdown) => vec![
+            (
+                &markup::RE_RST_CODE,
+                Box::new(|captures: &Captures| format!("`{}`", &captures[1])),
+            ),
+            (
+                &markup::RE_DOC_LINK,
+                Box::new(|captures: &Captures| {
+                    let (label, file) = markup::doc_link(captures);
+                    to.link(&label, targets.get(&file).unwrap_or(&file))
+                }),
+            ),
+            (
+                &markup::RE_RST_LINK,
+                Box::new(|captures: &Captures| {
+                    let label = if captures[1].is_empty() {
+                        &captures[2]
+                    } else {
+                        &captures[1]
+                    };
+                    match local_target(&captures[2], targets) {
+                        Some(target) => to.link(label, &target),
+                        None => format!("[{}]({})", label, &captures[2]),
+                    }
+                }),
+            ),
+        ],
+        _ => return text.to_string(),
+    };
+    // Restructured Text only has one way to write bold and italic text.
+    let emphasis: Vec<(&Regex, &str)> = match from {
+        Format::Markdown => vec![
+            (&markup::RE_DOUBLE_UNDERSCORE, "**$1**"),
+            (&markup::RE_UNDERSCORE, "*$1*"),
+        ],
+        _ => Vec::new(),
+    };
+
+    markup::replace_inline(text, &replacements, |text| {
+        let mut text = text.to_string();
+        for (pattern, replacement) in emphasis {
+            text = pattern.replace_all(&text, replacement).to_string();
+        }
+        text
+    })
+}
+
+// The file a link points at, if it's another document next to this one (rather than a web page),
+// renamed if it's one of the records being converted.
+fn local_target(target: &str, targets: &HashMap<String, String>) -> Option<String> {
+    if target.contains("://") || target.starts_with(['#', '/']) || target.starts_with("mailto:") {
+        return None;
+    }
+    match Path::new(target).extension().and_then(|e| e.to_str()) {
+        Some("md") | Some("rst") => Some(
+            targets
+                .get(target)
+                .cloned()
+                .unwrap_or_else(|| target.to_string()),
+        ),
+        _ => None,
+    }
+}
+
+// Move each heading which is below the top level into the body of the section above it, as a
+// Restructured Text heading underlined with punctuation.
+fn fold_subheadings(sections: Vec<Section>) -> Vec<Section> {
+    let top = sections
+        .iter()
+        .map(|section| section.level)
+        .min()
+        .unwrap_or(2);
+    let mut folded: Vec<Section> = Vec::new();
+    for section in sections {
+        match folded.last_mut() {
+            Some(parent) if section.level > top => {
+                let underline = UNDERLINES[(section.level - top - 1).min(UNDERLINES.len() - 1)];
+                push_blank(&mut parent.body);
+                parent.body.push(section.heading.clone());
+                parent.body.push(
+                    underline
+                        .to_string()
+                        .repeat(section.heading.chars().count()),
+                );
+                if !section.body.is_empty() {
+                    parent.body.push(String::new());
+                    parent.body.extend(section.body);
+                }
+            }
+            _ => folded.push(section),
+        }
+    }
+    folded
+}
+
+// Add a blank line, unless there is one already (or nothing at all).
+fn push_blank(lines: &mut Vec<String>) {
+    if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+        lines.push(String::new());
+    }
+}
@@ -0,0 +1,144 @@
+// A unified diff (as made by `diff -u` or `git diff`) between two versions of a file, used to
+// show what a dry run would change. Records are short, so the lines in common are found with the
+// simple longest common subsequence table rather than anything cleverer.
+
+// The number of unchanged lines shown around each change.
+const CONTEXT: usize = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Same,
+    Removed,
+    Added,
+}
+
+// One line of the diff, with the number of lines of the old and new file which come before it.
+struct Line<'a> {
+    edit: Edit,
+    text: &'a str,
+    old_position: usize,
+    new_position: usize,
+}
+
+// Produce the diff for the file named `label`. A `before` of None is a new file, and an `after` of
+// None is a removed file. Nothing is returned if the two are the same.
+pub fn unified_diff(label: &str, before: Option<&str>, after: Option<&str>) -> String {
+    let old: Vec<&str> = before
+        .map(|text| text.lines().collect())
+        .unwrap_or_default();
+    let new: Vec<&str> = after.map(|text| text.lines().collect()).unwrap_or_default();
+    let lines = edits(&old, &new);
+    if lines.iter().all(|line| line.edit == Edit::Same) {
+        return String::new();
+    }
+
+    let mut output: Vec<String> = vec![
+        match before {
+            Some(_) => format!("--- a/{}", label),
+            None => String::from("--- /dev/null"),
+        },
+        match after {
+            Some(_) => format!("+++ b/{}", label),
+            None => String::from("+++ /dev/null"),
+        },
+    ];
+
+    let is_change = |index: usize| lines[index].edit != Edit::Same;
+    let mut index = 0;
+    while index < lines.len() {
+        if !is_change(index) {
+            index += 1;
+            continue;
+        }
+
+        // Take in any later changes which are close enough to share their context with this one.
+        let start = index.saturating_sub(CONTEXT);
+        let mut last_change = index;
+        let mut next = index + 1;
+        while next < lines.len() && next - last_change <= CONTEXT * 2 {
+            if is_change(next) {
+                last_change = next;
+            }
+            next += 1;
+        }
+        let end = (last_change + 1 + CONTEXT).min(lines.len());
+        let hunk = &lines[start..end];
+
+        let old_length = hunk.iter().filter(|line| line.edit != Edit::Added).count();
+        let new_length = hunk
+            .iter()
+            .filter(|line| line.edit != Edit::Removed)
+            .count();
+        let old_start = hunk[0].old_position + usize::from(old_length > 0);
+        let new_start = hunk[0].new_position + usize::from(new_length > 0);
+        output.push(format!(
+            "@@ -{},{} +{},{} @@",
+            old_start, old_length, new_start, new_length
+        ));
+        for line in hunk {
+            let marker = match line.edit {
+                Edit::Same => ' ',
+                Edit::Removed => '-',
+                Edit::Added => '+',
+            };
+            output.push(format!("{}{}", marker, line.text));
+        }
+        index = end;
+    }
+
+    let mut content = output.join("\u{000A}");
+    content.push('\u{000A}');
+    content
+}
+
+// Work out which lines are kept, removed and added to turn `old` into `new`.
+fn edits<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Line<'a>> {
+    // common[i][j] is the length of the longest common subsequence of old[i..] and new[j..].
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines: Vec<Line> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        let line = if i < old.len() && j < new.len() && old[i] == new[j] {
+            Line {
+                edit: Edit::Same,
+                text: old[i],
+                old_position: i,
+                new_position: j,
+            }
+        } else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            Line {
+                edit: Edit::Removed,
+                text: old[i],
+                old_position: i,
+                new_position: j,
+            }
+        } else {
+            Line {
+                edit: Edit::Added,
+                text: new[j],
+                old_position: i,
+                new_position: j,
+            }
+        };
+        match line.edit {
+            Edit::Same => {
+                i += 1;
+                j += 1;
+            }
+            Edit::Removed => i += 1,
+            Edit::Added => j += 1,
+        }
+        lines.push(line);
+    }
+    lines
+}
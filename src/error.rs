@@ -0,0 +1,126 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+// Everything which can go wrong when working with decision records. Each has a message for the
+// person running the command, and its own exit code, so that scripts can tell them apart.
+#[derive(Debug)]
+pub enum Error {
+    // No .adr-dir, .decisionrecords-config, doc/adr or doc/decision_records was found in the
+    // directory given, or any of its parents.
+    NotInitialised(PathBuf),
+    // `init` was run where there's already a config file, without --force.
+    AlreadyInitialised(PathBuf),
+    // Nothing matches the number, file name, slug or title given.
+    RecordNotFound(String),
+    // More than one record matches what was given.
+    AmbiguousRecord {
+        record: String,
+        matches: Vec<String>,
+    },
+    // A record with this slug already exists.
+    RecordExists(String),
+    // The status isn't one of the statuses in the config.
+    UnknownStatus {
+        status: String,
+        known: Vec<String>,
+    },
+    // The workflow in the config doesn't allow a record to move between these statuses.
+    InvalidTransition {
+        record: String,
+        from: String,
+        to: String,
+        allowed: Vec<String>,
+    },
+    // A config file which can't be understood.
+    ConfigParse {
+        path: PathBuf,
+        message: String,
+    },
+    // Some other value given (e.g. a date) which can't be used.
+    InvalidInput(String),
+    // Reading or writing a file failed.
+    Io(io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    // The exit code for this error. 1 is any other failure, and 2 is a mistake in the command
+    // line (reported by clap before any of these can happen).
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Io(_) => 1,
+            Error::NotInitialised(_) => 3,
+            Error::AlreadyInitialised(_) => 4,
+            Error::RecordNotFound(_) => 5,
+            Error::AmbiguousRecord { .. } => 6,
+            Error::RecordExists(_) => 7,
+            Error::UnknownStatus { .. } | Error::InvalidTransition { .. } => 8,
+            Error::ConfigParse { .. } => 9,
+            Error::InvalidInput(_) => 10,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::NotInitialised(path) => write!(
+                f,
+                "No decision records found in {} or any directory above it. Run `decision-record init` to start.",
+                path.display()
+            ),
+            Error::AlreadyInitialised(path) => write!(
+                f,
+                "{} already exists. Use --force to replace it.",
+                path.display()
+            ),
+            Error::RecordNotFound(record) => write!(f, "No record found matching \"{}\"", record),
+            Error::AmbiguousRecord { record, matches } => write!(
+                f,
+                "\"{}\" matches more than one record: {}",
+                record,
+                matches.join(", ")
+            ),
+            Error::RecordExists(slug) => write!(f, "A record called {} already exists", slug),
+            Error::UnknownStatus { status, known } => write!(
+                f,
+                "\"{}\" is not a known status. The known statuses are: {}",
+                status,
+                known.join(", ")
+            ),
+            Error::InvalidTransition {
+                record,
+                from,
+                to,
+                allowed,
+            } => {
+                write!(f, "{} can't be moved from {} to {}; ", record, from, to)?;
+                if allowed.is_empty() {
+                    write!(f, "it can't be moved to any other status")
+                } else {
+                    write!(f, "it can only be moved to {}", allowed.join(", "))
+                }
+            }
+            Error::ConfigParse { path, message } => write!(f, "{}: {}", path.display(), message),
+            Error::InvalidInput(message) => write!(f, "{}", message),
+            Error::Io(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
@@ -0,0 +1,479 @@
+// Export the records as a static HTML site: an index page, grouped by status, with a search box,
+// and a page for each record, with its links to other records. Everything (the styles and the
+// search script) is inside the pages, so the directory can be copied to any web server, or opened
+// straight from the disk.
+
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::error::Error;
+use crate::generate::group_by_status;
+use crate::graph::status_colour;
+use crate::markup::{self, Replacement};
+use crate::record::{Format, Record};
+use crate::repository::Repository;
+
+const STYLE: &str = "body{font-family:system-ui,-apple-system,\"Segoe UI\",sans-serif;line-height:1.5;margin:0;color:#222;background:#fafafa}
+nav{background:#2f3e4e;padding:.6em 1.5em}nav a{color:#fff;text-decoration:none;font-weight:600}
+main{max-width:50em;margin:0 auto;padding:1em 1.5em 3em;background:#fff;min-height:100vh}
+a{color:#0b5cad}pre{background:#f3f3f3;padding:.8em;overflow-x:auto}code{background:#f3f3f3;padding:0 .2em}
+pre code{padding:0}.status{display:inline-block;padding:0 .5em;border:1px solid #999;border-radius:.8em;font-size:.85em}
+.date{color:#666}.history p{margin:.2em 0}#search{width:100%;padding:.5em;font-size:1em;box-sizing:border-box}
+ul.records{list-style:none;padding:0}ul.records li{padding:.3em 0;border-bottom:1px solid #eee}";
+
+// Hide the records (and then the status groups) which don't contain every word typed.
+const SEARCH_SCRIPT: &str = "document.getElementById('search').addEventListener('input',function(){
+var words=this.value.toLowerCase().split(/\\s+/).filter(function(w){return w.length>0;});
+document.querySelectorAll('section.group').forEach(function(group){var shown=0;
+group.querySelectorAll('li').forEach(function(item){var text=item.getAttribute('data-search');
+var match=words.every(function(w){return text.indexOf(w)>=0;});item.style.display=match?'':'none';if(match){shown++;}});
+group.style.display=shown>0?'':'none';});});";
+
+// Write the site into `output_directory`, returning the files written.
+pub fn html(repository: &Repository, output_directory: &Path) -> Result<Vec<PathBuf>, Error> {
+    let config = repository.config();
+    let records = repository.records()?;
+    let mut files: Vec<(PathBuf, String)> = Vec::new();
+
+    for record in &records {
+        files.push((
+            output_directory.join(page_name(&record.path.display().to_string())),
+            record_page(record, config),
+        ));
+    }
+    files.push((
+        output_directory.join("index.html"),
+        index_page(repository, records)?,
+    ));
+
+    let written: Vec<PathBuf> = files.iter().map(|(path, _)| path.clone()).collect();
+    repository.write_files(files)?;
+    Ok(written)
+}
+
+fn index_page(repository: &Repository, records: Vec<Record>) -> Result<String, Error> {
+    let config = repository.config();
+    let title = config.translate("Decision Records");
+    let mut body: Vec<String> = vec![
+        format!("<h1>{}</h1>", escape(&title)),
+        format!(
+            "<input id=\"search\" type=\"search\" placeholder=\"{}\" autofocus>",
+            escape(&config.translate("Search"))
+        ),
+    ];
+
+    for (status, group) in group_by_status(repository, records) {
+        body.push(String::from("<section class=\"group\">"));
+        body.push(format!("<h2>{}</h2>", escape(&config.translate(&status))));
+        body.push(String::from("<ul class=\"records\">"));
+        for record in &group {
+            let mut search_text: Vec<String> = vec![record.link_text(), status.clone()];
+            for section in &record.sections {
+                search_text.push(section.heading.clone());
+                search_text.extend(section.body.iter().cloned());
+            }
+            body.push(format!(
+                "<li data-search=\"{}\"><a href=\"{}\">{}</a> <span class=\"date\">{}</span></li>",
+                escape(&search_text.join(" ").to_lowercase()),
+                escape(&page_name(&record.path.display().to_string())),
+                escape(&record.link_text()),
+                escape(&record.date)
+            ));
+        }
+        body.push(String::from("</ul>"));
+        body.push(String::from("</section>"));
+    }
+    body.push(format!("<script>{}</script>", SEARCH_SCRIPT));
+
+    Ok(page(config, &title, &body.join("\u{000A}")))
+}
+
+fn record_page(record: &Record, config: &Config) -> String {
+    let status = record.status_name(config);
+    let mut body: Vec<String> = vec![
+        format!("<h1>{}</h1>", escape(&record.link_text())),
+        format!(
+            "<p><span class=\"status\" style=\"background:{}\">{}</span> <span class=\"date\">{}</span></p>",
+            status_colour(&status),
+            escape(&config.translate(&status)),
+            escape(&record.date)
+        ),
+    ];
+
+    for section in &record.sections {
+        let level = match record.format {
+            Format::ReStructuredText => 2,
+            _ => section.level.clamp(2, 6),
+        };
+        body.push(format!(
+            "<h{}>{}</h{}>",
+            level,
+            render_inline(&section.heading, record.format),
+            level
+        ));
+        if section.heading == record.status_heading() {
+            body.push(String::from("<div class=\"history\">"));
+            for line in record.status_history() {
+                body.push(format!("<p>{}</p>", escape(&line)));
+            }
+            for relation in &record.relations {
+                let link = match href(&relation.target) {
+                    Some(href) => format!("<a href=\"{}\">{}</a>", href, escape(&relation.title)),
+                    None => escape(&relation.title),
+                };
+                let mut line = escape(&config.translate(relation.kind.template()));
+                if let Some(reason) = &relation.reason {
+                    line.push(' ');
+                    line.push_str(&escape(&config.translate("for the reason %")).replacen(
+                        '%',
+                        &escape(reason),
+                        1,
+                    ));
+                }
+                body.push(format!("<p>{}</p>", line.replacen('#', &link, 1)));
+            }
+            body.push(String::from("</div>"));
+        } else {
+            body.push(render_blocks(&section.body, record.format));
+        }
+    }
+
+    page(config, &record.link_text(), &body.join("\u{000A}"))
+}
+
+fn page(config: &Config, title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>
+<html lang=\"{}\">
+<head>
+<meta charset=\"utf-8\">
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">
+<title>{}</title>
+<style>
+{}
+</style>
+</head>
+<body>
+<nav><a href=\"index.html\">{}</a></nav>
+<main>
+{}
+</main>
+</body>
+</html>
+",
+        escape(&config.template_language),
+        escape(title),
+        STYLE,
+        escape(&config.translate("Decision Records")),
+        body
+    )
+}
+
+// The page for a record, or the link to it: `0001-use-foo.md` becomes `0001-use-foo.html`. Links to
+// anything else (e.g. a web site) are left as they are.
+fn page_name(target: &str) -> String {
+    let path = Path::new(target);
+    let is_record = matches!(
+        path.extension().and_then(|extension| extension.to_str()),
+        Some("md" | "rst" | "adoc")
+    );
+    if target.contains("://") || !is_record {
+        return target.to_string();
+    }
+    match path.file_stem() {
+        Some(stem) => format!("{}.html", stem.to_string_lossy()),
+        None => target.to_string(),
+    }
+}
+
+// The (escaped) `href` for a link to `target`, or None if it isn't a relative path or a web or
+// e-mail address, so that e.g. a `javascript:` link in a record can't run in the page.
+fn href(target: &str) -> Option<String> {
+    // Browsers ignore white space and control characters when they read the scheme.
+    let scheme: String = target
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && !c.is_ascii_control())
+        .take_while(|c| !matches!(c, '/' | '?' | '#'))
+        .collect();
+    let allowed = match scheme.split_once(':') {
+        Some((scheme, _)) => {
+            let scheme = scheme.to_ascii_lowercase();
+            scheme == "http" || scheme == "https" || scheme == "mailto"
+        }
+        None => true,
+    };
+    if allowed {
+        Some(escape(&page_name(target)))
+    } else {
+        None
+    }
+}
+
+// Turn the text of a section into HTML paragraphs, lists and code blocks.
+fn render_blocks(lines: &[String], format: Format) -> String {
+    let re_item: &Regex = match format {
+        Format::Markdown => &markup::RE_MARKDOWN_ITEM,
+        Format::ReStructuredText => &markup::RE_RST_ITEM,
+        Format::AsciiDoc => &RE_ADOC_ITEM,
+    };
+
+    let mut html: Vec<String> = Vec::new();
+    let mut paragraph: Vec<String> = Vec::new();
+    // Whether the list is numbered, and its items.
+    let mut list: Option<(bool, Vec<String>)> = None;
+    // A Restructured Text paragraph ending in "::" is followed by a literal block.
+    let mut literal_next = false;
+
+    let mut index = 0;
+    while index < lines.len() {
+        let line = &lines[index];
+        let trimmed = line.trim();
+
+        // Code blocks.
+        let fence = match format {
+            Format::Markdown if trimmed.starts_with("```") || trimmed.starts_with("~~~") => {
+                Some(trimmed[..3].to_string())
+            }
+            Format::AsciiDoc if trimmed.starts_with("----") || trimmed.starts_with("```") => {
+                Some(trimmed.to_string())
+            }
+            _ => None,
+        };
+        if let Some(fence) = fence {
+            flush(&mut html, &mut paragraph, &mut list, format);
+            let mut code: Vec<&str> = Vec::new();
+            index += 1;
+            while index < lines.len() && !lines[index].trim().starts_with(&fence) {
+                code.push(&lines[index]);
+                index += 1;
+            }
+            html.push(code_block(&code));
+            index += 1;
+            continue;
+        }
+        let directive = match format {
+            Format::ReStructuredText => markup::RE_CODE_DIRECTIVE.captures(line),
+            _ => None,
+        };
+        let starts_literal = literal_next && line.starts_with([' ', '\t']);
+        if directive.is_some() || starts_literal {
+            flush(&mut html, &mut paragraph, &mut list, format);
+            literal_next = false;
+            let indent = directive
+                .as_ref()
+                .map_or(0, |captures| captures[1].chars().count());
+            let (code, _) = markup::literal_block(lines, &mut index, indent, directive.is_some());
+            let code: Vec<&str> = code.iter().map(String::as_str).collect();
+            html.push(code_block(&code));
+            continue;
+        }
+
+        // Comments, and AsciiDoc block attributes like `[source,rust]`.
+        let is_comment = match format {
+            Format::Markdown => trimmed.starts_with("<!--"),
+            Format::ReStructuredText => trimmed.starts_with(".. "),
+            Format::AsciiDoc => trimmed.starts_with("//") || RE_ADOC_ATTRIBUTE.is_match(trimmed),
+        };
+        if is_comment {
+            if format == Format::Markdown {
+                while index < lines.len() && !lines[index].contains("-->") {
+                    index += 1;
+                }
+            }
+            index += 1;
+            continue;
+        }
+
+        if trimmed.is_empty() {
+            let ends_literal = paragraph
+                .last()
+                .is_some_and(|line| markup::literal_marker(line).0);
+            flush_paragraph(&mut html, &mut paragraph, format);
+            literal_next = literal_next || ends_literal;
+            index += 1;
+            continue;
+        }
+        literal_next = false;
+
+        // A Restructured Text heading inside a section, underlined with punctuation.
+        if format == Format::ReStructuredText
+            && paragraph.is_empty()
+            && index + 1 < lines.len()
+            && markup::RE_UNDERLINE.is_match(lines[index + 1].trim())
+            && lines[index + 1].trim().chars().count() >= trimmed.chars().count()
+        {
+            flush(&mut html, &mut paragraph, &mut list, format);
+            html.push(format!("<h3>{}</h3>", render_inline(trimmed, format)));
+            index += 2;
+            continue;
+        }
+
+        if let Some(captures) = re_item.captures(line) {
+            flush_paragraph(&mut html, &mut paragraph, format);
+            let numbered = captures[2].starts_with(|c: char| c.is_ascii_digit() || c == '.')
+                || &captures[2] == "#.";
+            if list.as_ref().is_some_and(|(kind, _)| *kind != numbered) {
+                flush(&mut html, &mut paragraph, &mut list, format);
+            }
+            list.get_or_insert((numbered, Vec::new()))
+                .1
+                .push(captures[3].to_string());
+        } else if list.is_some() && paragraph.is_empty() && line.starts_with([' ', '\t']) {
+            // A continuation of the last list item.
+            if let Some(item) = list.as_mut().and_then(|(_, items)| items.last_mut()) {
+                item.push(' ');
+                item.push_str(trimmed);
+            }
+        } else {
+            if list.is_some() {
+                flush(&mut html, &mut paragraph, &mut list, format);
+            }
+            paragraph.push(trimmed.to_string());
+        }
+        index += 1;
+    }
+    flush(&mut html, &mut paragraph, &mut list, format);
+    html.join("\u{000A}")
+}
+
+fn flush(
+    html: &mut Vec<String>,
+    paragraph: &mut Vec<String>,
+    list: &mut Option<(bool, Vec<String>)>,
+    format: Format,
+) {
+    flush_paragraph(html, paragraph, format);
+    if let Some((numbered, items)) = list.take() {
+        let tag = if numbered { "ol" } else { "ul" };
+        html.push(format!("<{}>", tag));
+        for item in items {
+            html.push(format!("<li>{}</li>", render_inline(&item, format)));
+        }
+        html.push(format!("</{}>", tag));
+    }
+}
+
+fn flush_paragraph(html: &mut Vec<String>, paragraph: &mut Vec<String>, format: Format) {
+    if paragraph.is_empty() {
+        return;
+    }
+    let mut text = std::mem::take(paragraph).join(" ");
+    // "Like this::" in Restructured Text reads as "Like this:", and a lone "::" disappears.
+    if format == Format::ReStructuredText {
+        text = markup::literal_marker(&text).1.to_string();
+        if text.is_empty() {
+            return;
+        }
+    }
+    html.push(format!("<p>{}</p>", render_inline(&text, format)));
+}
+
+fn code_block(lines: &[&str]) -> String {
+    format!(
+        "<pre><code>{}</code></pre>",
+        escape(&lines.join("\u{000A}"))
+    )
+}
+
+lazy_static! {
+    // An AsciiDoc list item, capturing its indent, marker and text, as `markup::RE_MARKDOWN_ITEM`
+    // does for Markdown.
+    static ref RE_ADOC_ITEM: Regex = Regex::new(r"^(\s*)(\*+|-|\.+|\d+\.)\s+(.*)$").unwrap();
+    // An AsciiDoc attribute line, e.g. `[source,rust]`.
+    static ref RE_ADOC_ATTRIBUTE: Regex = Regex::new(r"^\[.*\]$").unwrap();
+    // AsciiDoc links to files and web sites, and cross references to other documents.
+    static ref RE_ADOC_LINK: Regex = Regex::new(
+        r"(?:xref:|link:)?([^\s\[\]]+\.(?:adoc|md|rst|html)|https?://[^\s\[\]]+)\[([^\]]*)\]"
+    )
+    .unwrap();
+    static ref RE_ADOC_CROSS_REFERENCE: Regex =
+        Regex::new(r"<<([^,>]+)(?:,\s*([^>]+))?>>").unwrap();
+}
+
+// Turn the inline markup in a line (code, links, bold and italic text) into HTML.
+fn render_inline(text: &str, format: Format) -> String {
+    let code = |captures: &Captures| format!("<code>{}</code>", escape(&captures[1]));
+    let link = |target: &str, label: &str| {
+        let label = if label.is_empty() { target } else { label };
+        match href(target) {
+            Some(href) => format!("<a href=\"{}\">{}</a>", href, escape(label)),
+            None => escape(label),
+        }
+    };
+    let replacements: Vec<(&Regex, Replacement)> = match format {
+        Format::Markdown => vec![
+            (&markup::RE_CODE, Box::new(code)),
+            (
+                &markup::RE_MARKDOWN_LINK,
+                Box::new(move |captures: &Captures| link(&captures[3], &captures[2])),
+            ),
+            (
+                &markup::RE_AUTOLINK,
+                Box::new(move |captures: &Captures| link(&captures[1], "")),
+            ),
+        ],
+        Format::ReStructuredText => vec![
+            (
+                &markup::RE_DOC_LINK,
+                Box::new(move |captures: &Captures| {
+                    let (label, file) = markup::doc_link(captures);
+                    link(&file, &label)
+                }),
+            ),
+            (
+                &markup::RE_RST_LINK,
+                Box::new(move |captures: &Captures| link(&captures[2], &captures[1])),
+            ),
+            (&markup::RE_RST_CODE, Box::new(code)),
+        ],
+        Format::AsciiDoc => vec![
+            (&markup::RE_CODE, Box::new(code)),
+            (
+                &RE_ADOC_LINK,
+                Box::new(move |captures: &Captures| link(&captures[1], &captures[2])),
+            ),
+            (
+                &RE_ADOC_CROSS_REFERENCE,
+                Box::new(move |captures: &Captures| {
+                    link(
+                        &captures[1],
+                        captures.get(2).map_or("", |label| label.as_str()),
+                    )
+                }),
+            ),
+        ],
+    };
+
+    let emphasis: Vec<(&Regex, &str)> = match format {
+        Format::Markdown => vec![
+            (&markup::RE_DOUBLE_STAR, "<strong>$1</strong>"),
+            (&markup::RE_DOUBLE_UNDERSCORE, "<strong>$1</strong>"),
+            (&markup::RE_STAR, "<em>$1</em>"),
+            (&markup::RE_UNDERSCORE, "<em>$1</em>"),
+        ],
+        Format::ReStructuredText => vec![
+            (&markup::RE_DOUBLE_STAR, "<strong>$1</strong>"),
+            (&markup::RE_STAR, "<em>$1</em>"),
+        ],
+        Format::AsciiDoc => vec![
+            (&markup::RE_STAR, "<strong>$1</strong>"),
+            (&markup::RE_UNDERSCORE, "<em>$1</em>"),
+        ],
+    };
+    markup::replace_inline(text, &replacements, |text| {
+        let mut html = escape(text);
+        for (pattern, replacement) in emphasis {
+            html = pattern.replace_all(&html, replacement).to_string();
+        }
+        html
+    })
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
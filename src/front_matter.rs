@@ -0,0 +1,304 @@
+// A small reader and writer for the YAML front matter block at the top of a record, e.g.
+//
+// ---
+// id: 3
+// title: Use bar instead of foo
+// status: approved
+// supersedes: [1]
+// tags: [storage]
+// ---
+//
+// Only the parts of YAML which front matter needs are handled: plain, single or double quoted
+// values, and lists written either as `[a, b]` or as `- a` lines below the key. The order of the
+// keys is kept, so that keys added by hand survive being written back. Anything else (comments,
+// nested maps, `|` and `>` blocks, lists of maps) is kept as the lines it was written as, and
+// written back unchanged.
+
+pub const DELIMITER: &str = "---";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+    Text(String),
+    List(Vec<String>),
+    // The lines of a value which isn't read, starting with the line holding its key.
+    Raw(Vec<String>),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FrontMatter {
+    // Comments, blank lines and anything else which isn't a key are kept as raw values with no key.
+    entries: Vec<(String, Value)>,
+}
+
+impl FrontMatter {
+    // Read the lines between the `---` delimiters.
+    pub fn parse(lines: &[String]) -> FrontMatter {
+        let mut front_matter = FrontMatter::default();
+
+        let mut index = 0;
+        while index < lines.len() {
+            let line = &lines[index];
+            let trimmed = line.trim();
+            index += 1;
+            let key_and_value = trimmed
+                .find(':')
+                .filter(|_| !line.starts_with([' ', '\t']) && !trimmed.starts_with(['#', '-']));
+            let position = match key_and_value {
+                Some(position) => position,
+                None => {
+                    front_matter
+                        .entries
+                        .push((String::new(), Value::Raw(vec![line.clone()])));
+                    continue;
+                }
+            };
+            let key = trimmed[..position].trim().to_string();
+            let value = trimmed[position + 1..].trim();
+
+            // The lines below the key which belong to it: indented lines, and `- item` lines.
+            let start = index;
+            while index < lines.len()
+                && (lines[index].starts_with([' ', '\t'])
+                    || lines[index].starts_with("- ")
+                    || lines[index] == "-"
+                    || (lines[index].trim().is_empty()
+                        && lines[index..]
+                            .iter()
+                            .find(|line| !line.trim().is_empty())
+                            .is_some_and(|line| line.starts_with([' ', '\t']))))
+            {
+                index += 1;
+            }
+            let below = &lines[start..index];
+
+            // A list is read if every item is plain text at the same indent, so lists of maps
+            // (`- name: x`) and nested lists are kept as they are.
+            let indent = |line: &str| line.len() - line.trim_start().len();
+            let first_indent = below
+                .iter()
+                .find(|line| !line.trim().is_empty())
+                .map(|line| indent(line));
+            let items: Option<Vec<String>> = below
+                .iter()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| {
+                    line.trim()
+                        .strip_prefix("- ")
+                        .filter(|item| Some(indent(line)) == first_indent && is_scalar(item))
+                        .filter(|item| item.starts_with(['"', '\'']) || !item.contains(": "))
+                        .map(unquote)
+                })
+                .collect();
+            let value = match items {
+                Some(items) if value.is_empty() && !items.is_empty() => Value::List(items),
+                _ if !below.is_empty() || !is_scalar(value) => {
+                    Value::Raw(lines[start - 1..index].to_vec())
+                }
+                _ if value.starts_with('[') && value.ends_with(']') => Value::List(
+                    split_list(&value[1..value.len() - 1])
+                        .iter()
+                        .map(|item| unquote(item))
+                        .collect(),
+                ),
+                _ => Value::Text(unquote(value)),
+            };
+            front_matter.entries.push((key, value));
+        }
+        front_matter
+    }
+
+    // Write the front matter out, including the `---` delimiters.
+    pub fn render(&self) -> Vec<String> {
+        let mut lines = vec![String::from(DELIMITER)];
+        for (key, value) in &self.entries {
+            match value {
+                Value::Text(text) => lines.push(format!("{}: {}", key, quote(text))),
+                Value::List(items) => lines.push(format!(
+                    "{}: [{}]",
+                    key,
+                    items
+                        .iter()
+                        .map(|item| quote(item))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                )),
+                Value::Raw(raw) => lines.extend(raw.iter().cloned()),
+            }
+        }
+        lines.push(String::from(DELIMITER));
+        lines
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.entries
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value)
+    }
+
+    pub fn text(&self, key: &str) -> Option<&str> {
+        match self.get(key) {
+            Some(Value::Text(text)) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn list(&self, key: &str) -> Vec<String> {
+        match self.get(key) {
+            Some(Value::List(items)) => items.clone(),
+            Some(Value::Text(text)) if !text.is_empty() => vec![text.clone()],
+            _ => Vec::new(),
+        }
+    }
+
+    // Set a value, replacing the existing value in place, or adding the key at the end.
+    pub fn set(&mut self, key: &str, value: Value) {
+        match self.entries.iter_mut().find(|(name, _)| name == key) {
+            Some((_, existing)) => *existing = value,
+            None => self.entries.push((key.to_string(), value)),
+        }
+    }
+}
+
+// Whether a value on the line of its key is one which is read: plain or quoted text, or a
+// `[a, b]` list. Block scalars (`|`, `>`), maps (`{a: b}`) and the like are kept as they are.
+fn is_scalar(value: &str) -> bool {
+    if let Some(list) = value.strip_prefix('[') {
+        return list.strip_suffix(']').is_some_and(|inside| {
+            split_list(inside).iter().all(|item| {
+                let item = item.trim();
+                item.starts_with(['"', '\'']) || !item.contains(['[', '{'])
+            })
+        });
+    }
+    !value.starts_with(['|', '>', '{', '&', '*', '!'])
+}
+
+// Split the inside of a `[a, "b, c"]` list on the commas which aren't inside quotes.
+fn split_list(text: &str) -> Vec<String> {
+    let mut items: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut quote_character: Option<char> = None;
+    let mut escaped = false;
+
+    for character in text.chars() {
+        match (quote_character, character) {
+            // A `\"` inside double quotes doesn't end them.
+            (Some('"'), _) if escaped => {
+                escaped = false;
+                current.push(character);
+            }
+            (Some('"'), '\\') => {
+                escaped = true;
+                current.push(character);
+            }
+            (None, ',') => items.push(std::mem::take(&mut current)),
+            (None, '"') | (None, '\'') => {
+                quote_character = Some(character);
+                current.push(character);
+            }
+            (Some(open), _) if open == character => {
+                quote_character = None;
+                current.push(character);
+            }
+            _ => current.push(character),
+        }
+    }
+    items.push(current);
+    items
+        .into_iter()
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn unquote(text: &str) -> String {
+    let text = text.trim();
+    if text.len() >= 2 && text.starts_with('"') && text.ends_with('"') {
+        text[1..text.len() - 1]
+            .replace("\\\"", "\"")
+            .replace("\\\\", "\\")
+    } else if text.len() >= 2 && text.starts_with('\'') && text.ends_with('\'') {
+        text[1..text.len() - 1].replace("''", "'")
+    } else {
+        text.to_string()
+    }
+}
+
+// Quote a value if YAML would otherwise read it as something other than the plain text.
+fn quote(text: &str) -> String {
+    let needs_quotes = text.is_empty()
+        || text != text.trim()
+        || text.contains(": ")
+        || text.contains(" #")
+        || text.ends_with(':')
+        || text.starts_with(|character: char| "-?:,[]{}#&*!|>'\"%@`".contains(character))
+        || text.contains(|character: char| "[]{},".contains(character));
+    if needs_quotes {
+        format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn keeps_what_it_does_not_read() {
+        let yaml = lines(
+            "# Written by hand
+status: accepted
+owner:
+  team: platform
+  lead: Sam
+summary: |
+  First line.
+
+  Second paragraph.
+reviewers:
+  - name: Ada
+    role: lead
+tags: [storage, cache]
+consulted:
+  - Bob
+  - Carol",
+        );
+        let mut front_matter = FrontMatter::parse(&yaml);
+        assert_eq!(front_matter.text("status"), Some("accepted"));
+        assert_eq!(front_matter.list("tags"), vec!["storage", "cache"]);
+        assert_eq!(front_matter.list("consulted"), vec!["Bob", "Carol"]);
+        assert_eq!(front_matter.text("owner"), None);
+
+        front_matter.set("status", Value::Text(String::from("superseded")));
+        let rendered = front_matter.render();
+        let expected = lines(
+            "---
+# Written by hand
+status: superseded
+owner:
+  team: platform
+  lead: Sam
+summary: |
+  First line.
+
+  Second paragraph.
+reviewers:
+  - name: Ada
+    role: lead
+tags: [storage, cache]
+consulted: [Bob, Carol]
+---",
+        );
+        assert_eq!(rendered, expected);
+        assert_eq!(
+            FrontMatter::parse(&rendered[1..rendered.len() - 1]),
+            front_matter
+        );
+    }
+}
@@ -0,0 +1,237 @@
+use std::fs::read_to_string;
+use std::path::Path;
+
+use crate::error::Error;
+use crate::record::{Format, Record};
+use crate::repository::Repository;
+
+// The comments which mark the start and end of the index inside an existing file, so that only
+// the text between them is replaced.
+const TOC_START: &str = "decision-records-toc start";
+const TOC_END: &str = "decision-records-toc end";
+
+// Build an index of every record, as a Markdown or AsciiDoc list, or a Restructured Text toctree.
+// The links are made relative to `base_path`, which is the directory the index will be written
+// into. When `by_status` is set, the records are grouped under a heading for each status, and when
+// `with_title` is set, the index starts with a "Decision Records" title.
+pub fn toc(
+    repository: &Repository,
+    format: Format,
+    base_path: &Path,
+    by_status: bool,
+    with_title: bool,
+) -> Result<String, Error> {
+    let config = repository.config();
+    let records = repository.records()?;
+    let mut lines: Vec<String> = Vec::new();
+
+    if with_title {
+        let title = config.translate("Decision Records");
+        match format {
+            Format::Markdown => lines.push(format!("# {}", title)),
+            Format::ReStructuredText => {
+                let rule = "#".repeat(title.chars().count());
+                lines.push(rule.clone());
+                lines.push(title);
+                lines.push(rule);
+            }
+            Format::AsciiDoc => lines.push(format!("= {}", title)),
+        }
+        lines.push(String::new());
+    }
+
+    if by_status {
+        for (status, group) in group_by_status(repository, records) {
+            let heading = config.translate(&status);
+            match format {
+                Format::Markdown => lines.push(format!("## {}", heading)),
+                Format::ReStructuredText => {
+                    lines.push(heading.clone());
+                    lines.push("=".repeat(heading.chars().count()));
+                }
+                Format::AsciiDoc => lines.push(format!("== {}", heading)),
+            }
+            lines.push(String::new());
+            lines.extend(toc_entries(&group, format, base_path));
+            lines.push(String::new());
+        }
+        lines.pop();
+    } else {
+        lines.extend(toc_entries(&records, format, base_path));
+    }
+
+    let mut content = lines.join("\u{000A}");
+    content.push('\u{000A}');
+    Ok(content)
+}
+
+// Write the index into an existing file, replacing only the text between the marker comments,
+// which should look like `<!-- decision-records-toc start -->` and
+// `<!-- decision-records-toc end -->` in Markdown, or `.. decision-records-toc start` and
+// `.. decision-records-toc end` in Restructured Text, or `// decision-records-toc start` and
+// `// decision-records-toc end` in AsciiDoc (or `# decision-records-toc start` and
+// `# decision-records-toc end` in YAML, e.g. mkdocs.yml). The file is written through the
+// repository, so it's left alone in a dry run.
+pub fn write_between_markers(
+    repository: &Repository,
+    path: &Path,
+    content: &str,
+) -> Result<(), Error> {
+    let existing = read_to_string(path)?;
+    let mut new_content: Vec<&str> = Vec::new();
+    let mut found_start = false;
+    let mut found_end = false;
+
+    for line in existing.lines() {
+        if found_start && !found_end {
+            if line.contains(TOC_END) {
+                found_end = true;
+                new_content.extend(content.lines());
+                new_content.push(line);
+            }
+        } else {
+            if line.contains(TOC_START) && !found_start {
+                found_start = true;
+            }
+            new_content.push(line);
+        }
+    }
+
+    if !found_start || !found_end {
+        return Err(Error::InvalidInput(format!(
+            "Unable to find the \"{}\" and \"{}\" markers in {}",
+            TOC_START,
+            TOC_END,
+            path.display()
+        )));
+    }
+
+    let mut new_file_content = new_content.join("\u{000A}");
+    new_file_content.push('\u{000A}');
+    repository.write_file(path, new_file_content)
+}
+
+fn toc_entries(records: &[Record], format: Format, base_path: &Path) -> Vec<String> {
+    match format {
+        Format::Markdown | Format::AsciiDoc => records
+            .iter()
+            .map(|record| {
+                format!(
+                    "* {}",
+                    format.link(&record.link_text(), &record.relative_path(base_path))
+                )
+            })
+            .collect(),
+        Format::ReStructuredText => {
+            let mut lines = vec![
+                String::from(".. toctree::"),
+                String::from("   :maxdepth: 1"),
+                String::new(),
+            ];
+            for record in records {
+                let document = record.relative_path(base_path);
+                let document = document
+                    .strip_suffix(&format!(".{}", record.format.extension()))
+                    .unwrap_or(&document);
+                lines.push(format!("   {} <{}>", record.link_text(), document));
+            }
+            lines
+        }
+    }
+}
+
+// The documentation site generators which a navigation block can be made for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NavFormat {
+    MdBook,
+    MkDocs,
+    Sphinx,
+}
+
+impl NavFormat {
+    pub fn from_name(name: &str) -> Option<NavFormat> {
+        match name {
+            "mdbook" => Some(NavFormat::MdBook),
+            "mkdocs" => Some(NavFormat::MkDocs),
+            "sphinx" => Some(NavFormat::Sphinx),
+            _ => None,
+        }
+    }
+}
+
+// Build the navigation for a documentation site, listing every record in number order under a
+// "Decision Records" heading: a part of mdBook's `SUMMARY.md`, the `nav:` block of `mkdocs.yml`,
+// or a Sphinx `toctree`. The paths are made relative to `base_path`, which is the directory the
+// site is built from (e.g. mdBook's `src`, or MkDocs' `docs_dir`).
+pub fn nav(repository: &Repository, format: NavFormat, base_path: &Path) -> Result<String, Error> {
+    let title = repository.config().translate("Decision Records");
+    let records = repository.records()?;
+    let mut lines: Vec<String> = Vec::new();
+
+    match format {
+        NavFormat::MdBook => {
+            lines.push(format!("# {}", title));
+            lines.push(String::new());
+            for record in &records {
+                lines.push(format!(
+                    "- {}",
+                    Format::Markdown.link(&record.link_text(), &record.relative_path(base_path))
+                ));
+            }
+        }
+        NavFormat::MkDocs => {
+            lines.push(String::from("nav:"));
+            lines.push(format!("  - {}:", yaml_quote(&title)));
+            for record in &records {
+                lines.push(format!(
+                    "    - {}: {}",
+                    yaml_quote(&record.link_text()),
+                    yaml_quote(&record.relative_path(base_path))
+                ));
+            }
+        }
+        NavFormat::Sphinx => {
+            lines.extend(toc_entries(&records, Format::ReStructuredText, base_path));
+            lines.insert(2, format!("   :caption: {}", title));
+        }
+    }
+
+    let mut content = lines.join("\u{000A}");
+    content.push('\u{000A}');
+    Ok(content)
+}
+
+// Quote a YAML value, so that titles holding a ": " or starting with a number stay text.
+fn yaml_quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// Split the records up by the name of their status, keeping them in order within each group. The
+// groups follow the order of the statuses in the workflow, then Superseded and Deprecated, then
+// any other statuses.
+pub(crate) fn group_by_status(
+    repository: &Repository,
+    records: Vec<Record>,
+) -> Vec<(String, Vec<Record>)> {
+    let mut groups: Vec<(String, Vec<Record>)> = repository
+        .config()
+        .statuses
+        .iter()
+        .map(String::as_str)
+        .chain(["Superseded", "Deprecated"])
+        .map(|status| (status.to_string(), Vec::new()))
+        .collect();
+
+    for record in records {
+        let mut status = record.status_name(repository.config());
+        if status.is_empty() {
+            status = String::from("Unknown");
+        }
+        match groups.iter_mut().find(|(name, _)| *name == status) {
+            Some((_, group)) => group.push(record),
+            None => groups.push((status, vec![record])),
+        }
+    }
+    groups.retain(|(_, group)| !group.is_empty());
+    groups
+}
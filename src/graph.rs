@@ -0,0 +1,227 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::error::Error;
+use crate::record::RelationKind;
+use crate::repository::Repository;
+
+// The graph languages which can be produced.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+impl GraphFormat {
+    pub fn from_name(name: &str) -> Option<GraphFormat> {
+        match name {
+            "dot" | "graphviz" => Some(GraphFormat::Dot),
+            "mermaid" => Some(GraphFormat::Mermaid),
+            _ => None,
+        }
+    }
+}
+
+// A box in the graph, for a record, or for a link to a record which couldn't be found.
+struct Node {
+    id: String,
+    label: String,
+    status: String,
+}
+
+// Produce a directed graph of the records and the relations between them. Each pair of relations
+// (e.g. "Supersedes" on one record and "Superseded by" on the other) is drawn as a single arrow,
+// from the newer record to the one it supersedes, deprecates, amends or links to. The boxes are
+// coloured by the status of the record.
+pub fn graph(repository: &Repository, format: GraphFormat) -> Result<String, Error> {
+    let config = repository.config();
+    let records = repository.records()?;
+
+    // Work out the name each record will have in the graph, from the path used to link to it, so
+    // two records with the same number are still drawn as two boxes.
+    let mut nodes: Vec<Node> = Vec::new();
+    let mut node_ids: HashMap<String, String> = HashMap::new();
+    for record in &records {
+        let path = record.relative_path(&config.record_path);
+        let mut id = node_id(&path);
+        if nodes.iter().any(|node| node.id == id) {
+            id = format!("{}_{}", id, nodes.len());
+        }
+        node_ids.insert(path, id.clone());
+        nodes.push(Node {
+            id,
+            label: record.link_text(),
+            status: record.status_name(config),
+        });
+    }
+
+    // Collect the arrows, turning the "passive" relations around so both sides of a pair match.
+    let mut edges: BTreeSet<(String, String, RelationKind)> = BTreeSet::new();
+    for record in &records {
+        let this_id = node_ids[&record.relative_path(&config.record_path)].clone();
+        for relation in &record.relations {
+            let target_id = match node_ids.get(&relation.target) {
+                Some(id) => id.clone(),
+                None => {
+                    let id = format!("missing_{}", nodes.len());
+                    node_ids.insert(relation.target.clone(), id.clone());
+                    nodes.push(Node {
+                        id: id.clone(),
+                        label: relation.title.clone(),
+                        status: String::from("Missing"),
+                    });
+                    id
+                }
+            };
+            let edge = match relation.kind {
+                RelationKind::Supersedes | RelationKind::Deprecates | RelationKind::Amends => {
+                    (this_id.clone(), target_id, relation.kind)
+                }
+                RelationKind::SupersededBy
+                | RelationKind::DeprecatedBy
+                | RelationKind::AmendedBy => {
+                    (target_id, this_id.clone(), relation.kind.reciprocal())
+                }
+                // Links go both ways, so put the pair in a fixed order to only draw it once.
+                RelationKind::LinkedTo => {
+                    if this_id <= target_id {
+                        (this_id.clone(), target_id, relation.kind)
+                    } else {
+                        (target_id, this_id.clone(), relation.kind)
+                    }
+                }
+            };
+            edges.insert(edge);
+        }
+    }
+
+    // The label on each arrow is the translated relation, without the link marker.
+    let edge_label = |kind: RelationKind| config.translate(kind.template()).replace('#', "");
+    let mut lines: Vec<String> = Vec::new();
+
+    match format {
+        GraphFormat::Dot => {
+            lines.push(format!(
+                "digraph \"{}\" {{",
+                escape_dot(&config.translate("Decision Records"))
+            ));
+            lines.push(String::from("  rankdir=LR;"));
+            lines.push(String::from(
+                "  node [shape=box, style=\"rounded,filled\"];",
+            ));
+            for node in &nodes {
+                lines.push(format!(
+                    "  {} [label=\"{}\", fillcolor=\"{}\"];",
+                    node.id,
+                    escape_dot(&node.label),
+                    status_colour(&node.status)
+                ));
+            }
+            for (from, to, kind) in &edges {
+                let style = if *kind == RelationKind::LinkedTo {
+                    ", dir=none, style=dashed"
+                } else {
+                    ""
+                };
+                lines.push(format!(
+                    "  {} -> {} [label=\"{}\"{}];",
+                    from,
+                    to,
+                    escape_dot(edge_label(*kind).trim()),
+                    style
+                ));
+            }
+            lines.push(String::from("}"));
+        }
+        GraphFormat::Mermaid => {
+            lines.push(String::from("graph LR"));
+            for node in &nodes {
+                lines.push(format!(
+                    "  {}[\"{}\"]",
+                    node.id,
+                    escape_mermaid(&node.label)
+                ));
+            }
+            for (from, to, kind) in &edges {
+                let arrow = if *kind == RelationKind::LinkedTo {
+                    "-.-"
+                } else {
+                    "-->"
+                };
+                lines.push(format!(
+                    "  {} {}|\"{}\"| {}",
+                    from,
+                    arrow,
+                    escape_mermaid(edge_label(*kind).trim()),
+                    to
+                ));
+            }
+            let mut classes: Vec<String> = nodes.iter().map(|node| node.status.clone()).collect();
+            classes.sort();
+            classes.dedup();
+            for status in classes {
+                let members: Vec<&str> = nodes
+                    .iter()
+                    .filter(|node| node.status == status)
+                    .map(|node| node.id.as_str())
+                    .collect();
+                let class_name = status_class(&status);
+                lines.push(format!(
+                    "  classDef {} fill:{}",
+                    class_name,
+                    status_colour(&status)
+                ));
+                lines.push(format!("  class {} {}", members.join(","), class_name));
+            }
+        }
+    }
+
+    let mut content = lines.join("\u{000A}");
+    content.push('\u{000A}');
+    Ok(content)
+}
+
+// A name for the record at `path` which Graphviz and Mermaid will accept, e.g.
+// "record_0012_use_foo_md".
+fn node_id(path: &str) -> String {
+    format!("record_{}", status_class(path))
+}
+
+pub(crate) fn status_colour(status: &str) -> &'static str {
+    match status {
+        "Proposed" => "#fff2b3",
+        "Approved" => "#c6efce",
+        "Rejected" => "#ffc7ce",
+        "Superseded" => "#d9d9d9",
+        "Deprecated" => "#e6d5f2",
+        "Missing" => "#ffffff",
+        _ => "#ddebf7",
+    }
+}
+
+// Turn a status into a name Mermaid will accept as a class, e.g. "In Review" to "in_review".
+fn status_class(status: &str) -> String {
+    let class: String = status
+        .to_lowercase()
+        .chars()
+        .map(|character| {
+            if character.is_ascii_alphanumeric() {
+                character
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if class.is_empty() {
+        String::from("unknown")
+    } else {
+        class
+    }
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_mermaid(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
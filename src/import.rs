@@ -0,0 +1,225 @@
+// Import the records written by the original bash adr-tools, so that they can be looked after by
+// this tool. adr-tools writes a bare status (e.g. "Accepted") with no date, and links which use
+// whatever wording was given to `adr new -l` (e.g. "Clarifies [2. Foo](0002-foo.md)"). These are
+// turned into dated status lines from the workflow, and links this tool knows, and a
+// `.decisionrecords-config` is written which points at the same directory.
+
+use chrono::NaiveDate;
+use pathdiff::diff_paths;
+use regex::Regex;
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+use crate::error::Error;
+use crate::lint::Problem;
+use crate::record::{madr_status_name, Format, Record, Relation, RelationKind};
+use crate::repository::Repository;
+
+// What an import changed, and what it couldn't convert.
+pub struct Import {
+    // The records which were rewritten.
+    pub converted: Vec<PathBuf>,
+    // The config file, if one was written (an existing one is left alone).
+    pub config_file: Option<PathBuf>,
+    pub problems: Vec<Problem>,
+}
+
+// Convert the adr-tools records found from `root` (through `.adr-dir` or `doc/adr`).
+pub fn adr_tools(repository: &Repository, root: &Path) -> Result<Import, Error> {
+    if !root.join(".adr-dir").exists() && !root.join("doc").join("adr").exists() {
+        return Err(Error::InvalidInput(format!(
+            "No adr-tools records found in {} (there's no .adr-dir or doc/adr)",
+            root.display()
+        )));
+    }
+
+    let config = repository.config();
+    let records = repository.records()?;
+    let re_date = Regex::new(r"\d{4}-\d{2}-\d{2}").unwrap();
+    let re_link = Regex::new(r"^\s*(.+?)\s+\[(.+?)\]\((.+?)\)\s*$").unwrap();
+    let titles: HashMap<String, String> = records
+        .iter()
+        .map(|record| {
+            (
+                record.relative_path(&config.record_path),
+                record.link_text(),
+            )
+        })
+        .collect();
+
+    // The config is written for the format most of the records are in.
+    let mut formats: Vec<(Format, usize)> = Vec::new();
+    for record in &records {
+        match formats
+            .iter_mut()
+            .find(|(format, _)| *format == record.format)
+        {
+            Some((_, count)) => *count += 1,
+            None => formats.push((record.format, 1)),
+        }
+    }
+    let file_type = formats
+        .iter()
+        .max_by_key(|(_, count)| *count)
+        .map_or("md", |(format, _)| format.extension());
+
+    let mut files: Vec<(PathBuf, String)> = Vec::new();
+    let mut converted: Vec<PathBuf> = Vec::new();
+    let mut imported: Vec<Record> = Vec::new();
+    let mut problems: Vec<Problem> = Vec::new();
+    let mut problem = |path: &Path, message: String| {
+        problems.push(Problem {
+            path: path.to_path_buf(),
+            message,
+        })
+    };
+
+    for mut record in records {
+        let date_is_valid = NaiveDate::parse_from_str(&record.date, "%Y-%m-%d").is_ok();
+        let mut status: Vec<String> = Vec::new();
+        for line in &record.status {
+            // Already converted, e.g. "Approved on 2020-01-01".
+            if re_date.is_match(line) {
+                status.push(line.clone());
+                continue;
+            }
+            // A link with wording this tool doesn't know becomes a "Linked to" link, keeping the
+            // wording as its reason.
+            if let Some(captures) = re_link.captures(line) {
+                record.relations.push(Relation {
+                    kind: RelationKind::LinkedTo,
+                    target: captures[3].to_string(),
+                    title: captures[2].to_string(),
+                    reason: Some(captures[1].to_string()),
+                });
+                problem(
+                    &record.path,
+                    format!(
+                        "\"{}\" isn't a kind of link this tool knows, so the link to {} is now \"Linked to\", with that as the reason",
+                        &captures[1], &captures[3]
+                    ),
+                );
+                continue;
+            }
+            match madr_status_name(line).and_then(|name| config.find_status(&name)) {
+                Some(name) if date_is_valid => {
+                    status.push(config.status_template(name).replace("DATE", &record.date))
+                }
+                Some(name) => {
+                    status.push(name.to_string());
+                    problem(
+                        &record.path,
+                        format!(
+                            "The date \"{}\" isn't YYYY-MM-DD, so the status \"{}\" has no date",
+                            record.date, name
+                        ),
+                    );
+                }
+                None => {
+                    status.push(line.clone());
+                    problem(
+                        &record.path,
+                        format!(
+                            "\"{}\" isn't a known status, so it has been left as it was",
+                            line.trim()
+                        ),
+                    );
+                }
+            }
+        }
+        record.status = status;
+
+        // Point each link at the record's current title, and report those which go nowhere.
+        for relation in &mut record.relations {
+            match titles.get(&relation.target) {
+                Some(title) => relation.title = title.clone(),
+                None => problem(
+                    &record.path,
+                    format!(
+                        "Links to {}, which doesn't exist, so it has been left as it was",
+                        relation.target
+                    ),
+                ),
+            }
+        }
+
+        imported.push(record);
+    }
+
+    // adr-tools only writes the other side of a link if it was given wording for it, so a "Linked
+    // to" link is added back from each record linked to this way, if it doesn't have one.
+    let mut links_back: Vec<(usize, Relation)> = Vec::new();
+    for record in &imported {
+        let target = record.relative_path(&config.record_path);
+        for relation in &record.relations {
+            if relation.kind != RelationKind::LinkedTo {
+                continue;
+            }
+            let Some(index) = imported
+                .iter()
+                .position(|other| other.relative_path(&config.record_path) == relation.target)
+            else {
+                continue;
+            };
+            let linked_back = imported[index]
+                .relations
+                .iter()
+                .chain(
+                    links_back
+                        .iter()
+                        .filter(|(other, _)| *other == index)
+                        .map(|(_, relation)| relation),
+                )
+                .any(|other| other.kind == RelationKind::LinkedTo && other.target == target);
+            if !linked_back {
+                let mut link_back =
+                    Relation::new(RelationKind::LinkedTo, record, &config.record_path);
+                link_back.reason = relation.reason.clone();
+                links_back.push((index, link_back));
+            }
+        }
+    }
+    for (index, relation) in links_back {
+        imported[index].add_relation(relation);
+    }
+
+    for record in imported {
+        let content = record.render(config);
+        if content != read_to_string(&record.path)? {
+            converted.push(record.path.clone());
+            files.push((record.path.clone(), content));
+        }
+    }
+
+    let config_file = root.join(".decisionrecords-config");
+    let mut config_written = None;
+    if !config_file.exists() {
+        let records_directory = diff_paths(&config.record_path, root)
+            .unwrap_or_else(|| config.record_path.clone())
+            .display()
+            .to_string();
+        let mut config_string = format!(
+            "records={}\u{000A}fileType={}\u{000A}defaultProposed=false\u{000A}",
+            records_directory, file_type
+        );
+        // adr-tools keeps its own template here, which uses the same NUMBER, TITLE, DATE and
+        // STATUS placeholders.
+        let templates = config.record_path.join("templates");
+        if templates.join("template.md").exists() {
+            config_string.push_str(&format!(
+                "templateDir={}/templates\u{000A}template=template\u{000A}",
+                records_directory
+            ));
+        }
+        files.push((config_file.clone(), config_string));
+        config_written = Some(config_file);
+    }
+
+    repository.write_files(files)?;
+    Ok(Import {
+        converted,
+        config_file: config_written,
+        problems,
+    })
+}
@@ -2,11 +2,14 @@ extern crate pathdiff;
 
 use pathdiff::diff_paths;
 use regex::Regex;
-use std::fs::{canonicalize, create_dir_all, remove_file, File};
+use std::fs::{canonicalize, create_dir_all, read_to_string, remove_file, File};
+use std::io;
 use std::io::prelude::*;
-use std::io::{Error, ErrorKind};
 use std::path::{Path, PathBuf};
 
+use crate::error::Error;
+use crate::repository::Repository;
+
 // In here we have two versions - the "short init" for use with the adr format
 // and the "normal init" used with the new decision-record format directory.
 
@@ -21,26 +24,24 @@ pub fn short_init(root_dir: PathBuf, doc_path: PathBuf, force: bool) -> Result<(
     absolute_config_path.push(absolute_root_dir);
     absolute_config_path.push(".adr-dir");
 
-    let relative_doc_path: String = diff_paths(absolute_doc_path, absolute_root_dir)
-        .unwrap()
-        .display()
-        .to_string();
+    let relative_doc_path: String = relative_path(absolute_doc_path, absolute_root_dir)?;
 
     if (absolute_config_path.exists() && force) || !absolute_config_path.exists() {
         if absolute_config_path.exists() && force {
-            remove_file(&absolute_config_path).unwrap();
+            remove_file(&absolute_config_path)?;
         }
-        create_file(absolute_config_path, relative_doc_path).unwrap();
+        create_file(absolute_config_path, relative_doc_path)?;
 
         if !absolute_doc_path.exists() {
-            create_dir_all(absolute_doc_path).unwrap();
+            create_dir_all(absolute_doc_path)?;
         }
     } else {
-        panic!("Unable to initialize directory, config file already exists");
+        return Err(Error::AlreadyInitialised(absolute_config_path));
     }
-    return Ok(());
+    Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn init(
     root_dir: PathBuf,
     doc_path: PathBuf,
@@ -60,27 +61,18 @@ pub fn init(
     absolute_config_path.push(absolute_root_dir);
     absolute_config_path.push(".decisionrecords-config");
 
-    let relative_doc_path = diff_paths(absolute_doc_path, absolute_root_dir)
-        .unwrap()
-        .display()
-        .to_string();
+    let relative_doc_path = relative_path(absolute_doc_path, absolute_root_dir)?;
 
     let mut config_string: String = "".to_string();
     config_string.push_str("records=");
     config_string.push_str(&relative_doc_path);
-    config_string.push_str("\u{000A}");
+    config_string.push('\u{000A}');
 
     config_string.push_str("templateDir=");
     config_string.push_str(template_directory);
-    config_string.push_str("\u{000A}");
+    config_string.push('\u{000A}');
 
-    println!(
-        "root_dir: {}",
-        canonicalize(absolute_root_dir)
-            .unwrap()
-            .display()
-            .to_string()
-    );
+    println!("root_dir: {}", canonicalize(absolute_root_dir)?.display());
     println!("doc_path: {}", str_doc_path);
     println!("relative_doc_path: {}", relative_doc_path);
     println!("template_file: {}", template_file);
@@ -91,74 +83,42 @@ pub fn init(
     println!("force: {}", force);
 
     let absolute_template_directory_path = absolute_root_dir.join(template_directory);
-    let mut complete_language_template_filename = String::from(template_file);
-    complete_language_template_filename.push_str(".");
-    complete_language_template_filename.push_str(language);
-    complete_language_template_filename.push_str(".");
-    let mut complete_language_template_ref = String::from(&complete_language_template_filename);
-    complete_language_template_ref.push_str("ref");
-    complete_language_template_filename.push_str(format);
-    let complete_absolute_template_path =
-        absolute_template_directory_path.join(&complete_language_template_filename);
-    let complete_language_template_ref_path =
-        absolute_template_directory_path.join(&complete_language_template_ref);
-
-    let re = Regex::new("([a-zA-Z]+)([-_][a-zA-Z]+)").unwrap();
-    let mut partial_language_template_filename = String::from(template_file);
-    partial_language_template_filename.push_str(".");
-    partial_language_template_filename.push_str(&re.replace(&language, "${1}"));
-    partial_language_template_filename.push_str(".");
-    let mut partial_language_template_ref = String::from(&partial_language_template_filename);
-    partial_language_template_ref.push_str("ref");
-    partial_language_template_filename.push_str(format);
-    let partial_absolute_template_path =
-        absolute_template_directory_path.join(&partial_language_template_filename);
-    let partial_language_template_ref_path =
-        absolute_template_directory_path.join(&partial_language_template_ref);
-
-    let mut no_language_template_filename = String::from(template_file);
-    no_language_template_filename.push_str(".");
-    let mut no_language_template_ref = String::from(&no_language_template_filename);
-    no_language_template_ref.push_str("ref");
-    no_language_template_filename.push_str(format);
-    let no_absolute_template_path =
-        absolute_template_directory_path.join(&no_language_template_filename);
-    let no_language_template_ref_path =
-        absolute_template_directory_path.join(&no_language_template_ref);
-
-    if language != "" {
+    let template_file_names = template_names(template_file, language, template_suffix(format));
+    let reference_file_names = template_names(template_file, language, "ref");
+
+    if !language.is_empty() {
         config_string.push_str("language=");
         config_string.push_str(language);
-        config_string.push_str("\u{000A}");
+        config_string.push('\u{000A}');
     }
 
-    if template_file != "" {
+    if !template_file.is_empty() {
         config_string.push_str("template=");
         config_string.push_str(template_file);
-        config_string.push_str("\u{000A}");
+        config_string.push('\u{000A}');
     }
 
-    if format != "" {
+    if !format.is_empty() {
         config_string.push_str("fileType=");
         config_string.push_str(format);
-        config_string.push_str("\u{000A}");
+        config_string.push('\u{000A}');
     }
 
     if default_proposed {
         config_string.push_str("defaultProposed=");
         config_string.push_str("true");
-        config_string.push_str("\u{000A}");
+        config_string.push('\u{000A}');
     } else {
         config_string.push_str("defaultProposed=");
         config_string.push_str("false");
-        config_string.push_str("\u{000A}");
+        config_string.push('\u{000A}');
     }
 
     println!("Checking config file");
     if (absolute_config_path.exists() && force) || !absolute_config_path.exists() {
         if absolute_config_path.exists() && force {
             println!("Found, removing...");
-            remove_file(&absolute_config_path).unwrap();
+            remove_file(&absolute_config_path)?;
         }
         println!("Checking Doc directory");
         if !absolute_doc_path.exists() {
@@ -176,7 +136,7 @@ pub fn init(
         if !absolute_template_directory_path.exists() {
             println!("Not found...");
             println!("Making Template directory");
-            let create_template_dir = create_dir_all(absolute_template_directory_path);
+            let create_template_dir = create_dir_all(&absolute_template_directory_path);
             if create_template_dir.is_ok() {
                 println!("Done");
             }
@@ -185,31 +145,32 @@ pub fn init(
         }
 
         println!("Checking Template File");
-        if !complete_absolute_template_path.exists()
-            && !partial_absolute_template_path.exists()
-            && !no_absolute_template_path.exists()
+        if !template_file_names
+            .iter()
+            .any(|name| absolute_template_directory_path.join(name).exists())
         {
             println!("Not found...");
-            let result_template = load_template(language.to_string(), format.to_string());
-            if result_template.is_ok() {
-                let template_string = result_template.unwrap();
+            if let Ok(template_string) = load_template(language.to_string(), format.to_string()) {
                 println!("Writing default template file");
-                let create_template = create_file(complete_absolute_template_path, template_string);
+                let create_template = create_file(
+                    absolute_template_directory_path.join(&template_file_names[0]),
+                    template_string,
+                );
                 if create_template.is_ok() {
                     println!("Done");
                 }
             }
         }
-        if !complete_language_template_ref_path.exists()
-            && !partial_language_template_ref_path.exists()
-            && !no_language_template_ref_path.exists()
+        if !reference_file_names
+            .iter()
+            .any(|name| absolute_template_directory_path.join(name).exists())
         {
-            let result_reference = load_template(language.to_string(), "ref".to_string());
-            if result_reference.is_ok() {
-                let template_string = result_reference.unwrap();
+            if let Ok(template_string) = load_template(language.to_string(), "ref".to_string()) {
                 println!("Writing default template reference");
-                let create_template =
-                    create_file(complete_language_template_ref_path, template_string);
+                let create_template = create_file(
+                    absolute_template_directory_path.join(&reference_file_names[0]),
+                    template_string,
+                );
                 if create_template.is_ok() {
                     println!("Done");
                 }
@@ -220,13 +181,160 @@ pub fn init(
         if create_file.is_ok() {
             println!("Done");
         }
-        return Ok(());
+        Ok(())
     } else {
-        panic!("Config file already exists");
+        Err(Error::AlreadyInitialised(absolute_config_path))
     }
 }
 
-fn create_file(filename: PathBuf, content: String) -> Result<(), Error> {
+// The choices made when moving a project from `.adr-dir` to `.decisionrecords-config`. These are
+// the same as the options to `init`.
+#[derive(Clone, Debug, Default)]
+pub struct Migration {
+    pub template_file: String,
+    // If this is empty, the format of the existing records is used (or md, if there are none).
+    pub format: String,
+    pub language: String,
+    // If this is not set, the templates go in `.template` inside the record directory.
+    pub template_directory: Option<String>,
+    pub default_proposed: bool,
+    // Remove `.adr-dir` once the new config is written. It can be kept for adr-tools, as
+    // `.decisionrecords-config` is read in its place.
+    pub remove_adr_dir: bool,
+    // Replace an existing `.decisionrecords-config`.
+    pub force: bool,
+}
+
+// Find the `.adr-dir` from this directory or one above it, and write a `.decisionrecords-config`
+// beside it for the same record directory, with the templates `init` would install. The files
+// are written through the repository, so nothing is changed in a dry run. The files written are
+// returned.
+pub fn migrate(
+    repository: &Repository,
+    path: &Path,
+    migration: &Migration,
+) -> Result<Vec<PathBuf>, Error> {
+    let root_dir = match path.ancestors().find(|dir| dir.join(".adr-dir").exists()) {
+        Some(root_dir) => root_dir.to_path_buf(),
+        None => {
+            return Err(Error::InvalidInput(format!(
+                "No .adr-dir found in {} or above it",
+                path.display()
+            )))
+        }
+    };
+    let adr_dir = root_dir.join(".adr-dir");
+    let config_path = root_dir.join(".decisionrecords-config");
+    if config_path.exists() && !migration.force {
+        return Err(Error::AlreadyInitialised(config_path));
+    }
+
+    // .adr-dir holds the record directory, relative to the directory it's in.
+    let records_directory = match read_to_string(&adr_dir)?
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+    {
+        Some(directory) => directory.replace('\\', "/"),
+        None => {
+            return Err(Error::ConfigParse {
+                path: adr_dir,
+                message: String::from("doesn't name a record directory"),
+            })
+        }
+    };
+    let format = if migration.format.is_empty() {
+        repository
+            .records()?
+            .first()
+            .map_or("md", |record| record.format.extension())
+            .to_string()
+    } else {
+        migration.format.clone()
+    };
+    let template_directory = migration
+        .template_directory
+        .clone()
+        .unwrap_or_else(|| format!("{}/.template", records_directory));
+
+    let mut config_string = format!(
+        "records={}\u{000A}templateDir={}\u{000A}",
+        records_directory, template_directory
+    );
+    if !migration.language.is_empty() {
+        config_string.push_str(&format!("language={}\u{000A}", migration.language));
+    }
+    if !migration.template_file.is_empty() {
+        config_string.push_str(&format!("template={}\u{000A}", migration.template_file));
+    }
+    config_string.push_str(&format!(
+        "fileType={}\u{000A}defaultProposed={}\u{000A}",
+        format, migration.default_proposed
+    ));
+    let mut files: Vec<(PathBuf, String)> = vec![(config_path, config_string)];
+
+    // Install the template and the language reference, unless there's one for the language (or
+    // its short form, or any language) already.
+    let template_path = root_dir.join(&template_directory);
+    for suffix in [template_suffix(&format), "ref"] {
+        let names = template_names(&migration.template_file, &migration.language, suffix);
+        if names.iter().any(|name| template_path.join(name).exists()) {
+            continue;
+        }
+        let kind = if suffix == "ref" { "ref" } else { &format };
+        if let Ok(template) = load_template(migration.language.clone(), kind.to_string()) {
+            files.push((template_path.join(&names[0]), template));
+        }
+    }
+
+    let written: Vec<PathBuf> = files.iter().map(|(path, _)| path.clone()).collect();
+    let removed = if migration.remove_adr_dir {
+        vec![adr_dir]
+    } else {
+        Vec::new()
+    };
+    repository.change_files(files, &removed)?;
+    Ok(written)
+}
+
+// The suffix of the template file for a format. MADR templates are Markdown, so they're stored
+// with the Markdown suffix.
+fn template_suffix(format: &str) -> &str {
+    if format == "madr" {
+        "md"
+    } else {
+        format
+    }
+}
+
+// The names a template file (or, with the suffix "ref", a language reference) can have, from the
+// most specific to the least: for the language (e.g. `template.en-GB.md`), for its short form
+// (`template.en.md`) and for any language (`template.md`). A new file is given the first name.
+fn template_names(template_file: &str, language: &str, suffix: &str) -> [String; 3] {
+    let short_language = Regex::new("([a-zA-Z]+)([-_][a-zA-Z]+)")
+        .unwrap()
+        .replace(language, "${1}")
+        .to_string();
+    [
+        format!("{}.{}.{}", template_file, language, suffix),
+        format!("{}.{}.{}", template_file, short_language, suffix),
+        format!("{}.{}", template_file, suffix),
+    ]
+}
+
+// The doc path as written into the config, relative to the root directory.
+fn relative_path(doc_path: &Path, root_dir: &Path) -> Result<String, Error> {
+    match diff_paths(doc_path, root_dir) {
+        Some(path) => Ok(path.display().to_string()),
+        None => Err(Error::InvalidInput(format!(
+            "Unable to find {} from {}",
+            doc_path.display(),
+            root_dir.display()
+        ))),
+    }
+}
+
+fn create_file(filename: PathBuf, content: String) -> Result<(), io::Error> {
     // write_all requires bytes. Convert content to bytes.
     let bytes_content = content.as_bytes();
     // Convert the filename to a (temporary) string
@@ -237,17 +345,57 @@ fn create_file(filename: PathBuf, content: String) -> Result<(), Error> {
     let mut file_object = File::create(path_filename)?;
     // And then write everything to the file
     file_object.write_all(bytes_content)?;
-    return Ok(());
+    Ok(())
 }
 
-fn load_template(language: String, format: String) -> Result<String, Error> {
+fn load_template(language: String, format: String) -> Result<String, io::Error> {
     let short_language = String::from(&language);
     let re = Regex::new("^([a-zA-Z]+)([-_][a-zA-Z]+|)$").unwrap();
     re.replace(&short_language, "${1}");
 
+    // MADR is only published in English, so the same template is used for every language.
+    if format == "madr" {
+        return Ok([
+            "---",
+            "status: STATUS",
+            "date: DATE",
+            "decision-makers: []",
+            "consulted: []",
+            "informed: []",
+            "---",
+            "",
+            "# NUMBER. TITLE",
+            "",
+            "## Context and Problem Statement",
+            "",
+            "Describe the context and problem statement, e.g., in free form using two to three sentences or in the form of an illustrative story.",
+            "",
+            "## Decision Drivers",
+            "",
+            "* Decision driver 1",
+            "* Decision driver 2",
+            "",
+            "## Considered Options",
+            "",
+            "* Option 1",
+            "* Option 2",
+            "",
+            "## Decision Outcome",
+            "",
+            "Chosen option: \"Option 1\", because it is the only option which meets the decision drivers.",
+            "",
+            "### Consequences",
+            "",
+            "* Good, because of this consequence.",
+            "* Bad, because of this other consequence.",
+            "",
+        ]
+        .join("\u{000A}"));
+    }
+
     if language == "en" || short_language == "en" {
         if format == "md" {
-            return Ok([
+            Ok([
                 "# NUMBER. TITLE",
                 "",
                 "Date: DATE",
@@ -269,9 +417,9 @@ fn load_template(language: String, format: String) -> Result<String, Error> {
                 "This is the consequence of the decision.",
                 "",
             ]
-            .join("\u{000A}"));
+            .join("\u{000A}"))
         } else if format == "rst" {
-            return Ok([
+            Ok([
                 "#################",
                 "NUMBER. TITLE",
                 "#################",
@@ -303,16 +451,37 @@ fn load_template(language: String, format: String) -> Result<String, Error> {
                 "This is the consequence of the decision.",
                 "",
             ]
-            .join("\u{000A}"));
+            .join("\u{000A}"))
+        } else if format == "adoc" {
+            Ok([
+                "= NUMBER. TITLE",
+                "",
+                "Date: DATE",
+                "",
+                "== Status",
+                "",
+                "STATUS",
+                "",
+                "== Context",
+                "",
+                "This is the context.",
+                "",
+                "== Decision",
+                "",
+                "This is the decision that was made.",
+                "",
+                "== Consequence",
+                "",
+                "This is the consequence of the decision.",
+                "",
+            ]
+            .join("\u{000A}"))
         } else {
-            return Err(Error::new(
-                ErrorKind::Other,
-                "Invalid Language/Format Match.",
-            ));
+            Err(io::Error::other("Invalid Language/Format Match."))
         }
     } else if language == "fr" || short_language == "fr" {
         if format == "md" {
-            return Ok([
+            Ok([
                 "# NUMBER. TITLE",
                 "",
                 "Date: DATE",
@@ -334,9 +503,33 @@ fn load_template(language: String, format: String) -> Result<String, Error> {
                 "C'est la conséquence de la décision.",
                 "",
             ]
-            .join("\u{000A}"));
+            .join("\u{000A}"))
+        } else if format == "adoc" {
+            Ok([
+                "= NUMBER. TITLE",
+                "",
+                "Date: DATE",
+                "",
+                "== Statut",
+                "",
+                "STATUS",
+                "",
+                "== Le contexte",
+                "",
+                "C'est le Contexte.",
+                "",
+                "== Décision",
+                "",
+                "Pris une décision.",
+                "",
+                "== Conséquence",
+                "",
+                "C'est la conséquence de la décision.",
+                "",
+            ]
+            .join("\u{000A}"))
         } else if format == "ref" {
-            return Ok([
+            Ok([
                 "Status=\"Statut\"",
                 "Context=\"Le contexte\"",
                 "Decision=\"Décision\"",
@@ -344,6 +537,9 @@ fn load_template(language: String, format: String) -> Result<String, Error> {
                 "Proposed on DATE=\"Proposé le DATE\"",
                 "Approved on DATE=\"Approuvé le DATE\"",
                 "Rejected on DATE=\"Rejeté le DATE\"",
+                "Superseded on DATE=\"Remplacé le DATE\"",
+                "Deprecated on DATE=\"Obsolète le DATE\"",
+                "by %=\"par %\"",
                 "Superseded by #=\"Remplacé par #\"",
                 "Supersedes #=\"Remplace #\"",
                 "Linked to #=\"Lié à #\"",
@@ -353,16 +549,13 @@ fn load_template(language: String, format: String) -> Result<String, Error> {
                 "Amends #=\"Modifie #\"",
                 "for the reason %=\"pour la raison %\"",
             ]
-            .join("\u{000A}"));
+            .join("\u{000A}"))
         } else {
-            return Err(Error::new(
-                ErrorKind::Other,
-                "Invalid Language/Format Match.",
-            ));
+            Err(io::Error::other("Invalid Language/Format Match."))
         }
     } else {
         if format == "md" {
-            return Ok([
+            Ok([
                 "# NUMBER. TITLE",
                 "",
                 "Date: DATE",
@@ -384,9 +577,9 @@ fn load_template(language: String, format: String) -> Result<String, Error> {
                 "This is the consequence of the decision.",
                 "",
             ]
-            .join("\u{000A}"));
+            .join("\u{000A}"))
         } else if format == "rst" {
-            return Ok([
+            Ok([
                 "#################",
                 "NUMBER. TITLE",
                 "#################",
@@ -418,12 +611,33 @@ fn load_template(language: String, format: String) -> Result<String, Error> {
                 "This is the consequence of the decision.",
                 "",
             ]
-            .join("\u{000A}"));
+            .join("\u{000A}"))
+        } else if format == "adoc" {
+            Ok([
+                "= NUMBER. TITLE",
+                "",
+                "Date: DATE",
+                "",
+                "== Status",
+                "",
+                "STATUS",
+                "",
+                "== Context",
+                "",
+                "This is the context.",
+                "",
+                "== Decision",
+                "",
+                "This is the decision that was made.",
+                "",
+                "== Consequence",
+                "",
+                "This is the consequence of the decision.",
+                "",
+            ]
+            .join("\u{000A}"))
         } else {
-            return Err(Error::new(
-                ErrorKind::Other,
-                "Invalid Language/Format Match.",
-            ));
+            Err(io::Error::other("Invalid Language/Format Match."))
         }
     }
 }
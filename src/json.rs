@@ -0,0 +1,204 @@
+// A small JSON writer for `--output json`, so that scripts and bots can read what a command did
+// without parsing the text meant for people. Only writing is needed, so there's no reader.
+//
+// Every record is written in the same shape (the schema is described in the README):
+//
+// {"number": 3, "id": "3", "slug": "use-bar", "title": "Use bar",
+//  "path": "doc/.../0003-use-bar.md", "format": "md", "date": "2024-01-31", "status": "Approved",
+//  "history": ["Approved on 2024-01-31"], "relations": [{"kind": "supersedes", "id": "1",
+//  "title": "1. Use foo", "target": "0001-use-foo.md", "reason": null}], "tags": []}
+
+use std::path::Path;
+
+use crate::config::Config;
+use crate::error::Error;
+use crate::front_matter::Value;
+use crate::record::{number_and_slug_from_path, Record, FRONT_MATTER_RELATIONS};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(i64),
+    String(String),
+    Array(Vec<Json>),
+    // The keys are kept in the order they're given, so the output is always the same.
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn object(entries: Vec<(&str, Json)>) -> Json {
+        Json::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    pub fn text(text: &str) -> Json {
+        Json::String(text.to_string())
+    }
+
+    pub fn texts(texts: &[String]) -> Json {
+        Json::Array(texts.iter().map(|text| Json::text(text)).collect())
+    }
+
+    // Write the value on a single line.
+    pub fn render(&self) -> String {
+        match self {
+            Json::Null => String::from("null"),
+            Json::Bool(value) => value.to_string(),
+            Json::Number(value) => value.to_string(),
+            Json::String(text) => escape(text),
+            Json::Array(items) => format!(
+                "[{}]",
+                items
+                    .iter()
+                    .map(Json::render)
+                    .collect::<Vec<String>>()
+                    .join(",")
+            ),
+            Json::Object(entries) => format!(
+                "{{{}}}",
+                entries
+                    .iter()
+                    .map(|(key, value)| format!("{}:{}", escape(key), value.render()))
+                    .collect::<Vec<String>>()
+                    .join(",")
+            ),
+        }
+    }
+}
+
+// A record, with its path given relative to `base_path` (normally the current directory).
+pub fn record(record: &Record, config: &Config, base_path: &Path) -> Json {
+    let mut relations: Vec<Json> = Vec::new();
+    for relation in &record.relations {
+        let kind = FRONT_MATTER_RELATIONS
+            .iter()
+            .find(|(_, kind)| *kind == relation.kind)
+            .map(|(key, _)| *key)
+            .unwrap_or_default();
+        let (number, slug) =
+            number_and_slug_from_path(Path::new(&relation.target), config.numbered);
+        relations.push(Json::object(vec![
+            ("kind", Json::text(kind)),
+            (
+                "id",
+                Json::String(if number > 0 { number.to_string() } else { slug }),
+            ),
+            ("title", Json::text(&relation.title)),
+            ("target", Json::text(&relation.target)),
+            (
+                "reason",
+                relation.reason.as_deref().map_or(Json::Null, Json::text),
+            ),
+        ]));
+    }
+
+    let tags = match record
+        .front_matter
+        .as_ref()
+        .and_then(|front_matter| front_matter.get("tags"))
+    {
+        Some(Value::List(tags)) => Json::texts(tags),
+        Some(Value::Text(tag)) if !tag.is_empty() => Json::Array(vec![Json::text(tag)]),
+        _ => Json::Array(Vec::new()),
+    };
+
+    Json::object(vec![
+        (
+            "number",
+            if record.number > 0 {
+                Json::Number(record.number.into())
+            } else {
+                Json::Null
+            },
+        ),
+        ("id", Json::String(record.identifier())),
+        ("slug", Json::text(&record.slug)),
+        ("title", Json::text(&record.title)),
+        ("path", Json::String(record.relative_path(base_path))),
+        ("format", Json::text(record.format.extension())),
+        ("date", Json::text(&record.date)),
+        ("status", Json::String(record.status_name(config))),
+        ("history", Json::texts(&record.status_history())),
+        ("relations", Json::Array(relations)),
+        ("tags", tags),
+    ])
+}
+
+// A record, as `record` gives it, followed by the text of each of its sections.
+pub fn record_with_sections(record: &Record, config: &Config, base_path: &Path) -> Json {
+    let mut json = self::record(record, config, base_path);
+    if let Json::Object(entries) = &mut json {
+        entries.push((
+            String::from("sections"),
+            Json::Array(
+                record
+                    .sections
+                    .iter()
+                    .map(|section| {
+                        // The Status block is kept apart from the sections, so it's written out
+                        // as it is in the file: each line as a paragraph of its own.
+                        let body = if section.heading == record.status_heading() {
+                            record.status_block(config).join("\u{000A}\u{000A}")
+                        } else {
+                            section.body.join("\u{000A}")
+                        };
+                        Json::object(vec![
+                            ("heading", Json::text(&section.heading)),
+                            ("level", Json::Number(section.level as i64)),
+                            ("body", Json::String(body)),
+                        ])
+                    })
+                    .collect(),
+            ),
+        ));
+    }
+    json
+}
+
+// An error, with the exit code the command will finish with.
+pub fn error(error: &Error) -> Json {
+    let kind = match error {
+        Error::NotInitialised(_) => "NotInitialised",
+        Error::AlreadyInitialised(_) => "AlreadyInitialised",
+        Error::RecordNotFound(_) => "RecordNotFound",
+        Error::AmbiguousRecord { .. } => "AmbiguousRecord",
+        Error::RecordExists(_) => "RecordExists",
+        Error::UnknownStatus { .. } => "UnknownStatus",
+        Error::InvalidTransition { .. } => "InvalidTransition",
+        Error::ConfigParse { .. } => "ConfigParse",
+        Error::InvalidInput(_) => "InvalidInput",
+        Error::Io(_) => "Io",
+    };
+    Json::object(vec![(
+        "error",
+        Json::object(vec![
+            ("kind", Json::text(kind)),
+            ("code", Json::Number(error.exit_code().into())),
+            ("message", Json::String(error.to_string())),
+        ]),
+    )])
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::from("\"");
+    for character in text.chars() {
+        match character {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\u{000A}' => escaped.push_str("\\n"),
+            '\u{000D}' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            character if (character as u32) < 0x20 => {
+                escaped.push_str(&format!("\\u{:04x}", character as u32))
+            }
+            character => escaped.push(character),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
@@ -0,0 +1,32 @@
+// Making Decision Records easier. This library holds everything the `decision-record` command
+// does, so that other tools can create, find and link decision records in the same way.
+//
+// Start with `Repository::open`, which finds the configuration for the records from a directory.
+//
+// Anything which can fail returns an `Error`, which says what went wrong (e.g. `RecordNotFound`).
+
+extern crate pathdiff;
+extern crate slug;
+
+pub mod config;
+pub mod convert;
+pub mod diff;
+pub mod error;
+pub mod export;
+pub mod front_matter;
+pub mod generate;
+pub mod graph;
+pub mod import;
+pub mod init;
+pub mod json;
+pub mod lint;
+mod markup;
+pub mod record;
+pub mod repository;
+pub mod staging;
+
+pub use config::Config;
+pub use error::{Error, Result};
+pub use record::{Format, Record, Relation, RelationKind, Section, Status};
+pub use repository::{NewRecord, Repository};
+pub use staging::FileChange;
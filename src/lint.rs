@@ -0,0 +1,99 @@
+use chrono::NaiveDate;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::error::Error;
+use crate::record::Record;
+use crate::repository::Repository;
+
+// Something found to be wrong with a record.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Problem {
+    pub path: PathBuf,
+    pub message: String,
+}
+
+// Check every record for the problems which creep in when records are edited by hand: headings
+// from the template which have gone missing, a date which can't be read, a status which isn't
+// one we know about, and links to records which don't exist or which don't link back.
+pub fn lint(repository: &Repository) -> Result<Vec<Problem>, Error> {
+    let config = repository.config();
+    let records = repository.records()?;
+    let mut problems: Vec<Problem> = Vec::new();
+
+    // The headings every record should have are the ones in the template.
+    let template_path = Path::new("template").with_extension(&config.template_format);
+    let template = Record::parse(&template_path, &config.template_string, config);
+
+    let mut known_statuses: Vec<String> = config.statuses.clone();
+    known_statuses.push(String::from("Superseded"));
+    known_statuses.push(String::from("Deprecated"));
+
+    // Index the records by the path used to link to them, so links can be followed.
+    let by_path: HashMap<String, &Record> = records
+        .iter()
+        .map(|record| (record.relative_path(&config.record_path), record))
+        .collect();
+
+    for record in &records {
+        let mut problem = |message: String| {
+            problems.push(Problem {
+                path: record.path.clone(),
+                message,
+            })
+        };
+
+        for section in &template.sections {
+            if record.section(&section.heading).is_none() {
+                problem(format!("Missing the \"{}\" heading", section.heading));
+            }
+        }
+
+        if record.date.is_empty() {
+            problem(String::from("Missing the \"Date:\" line"));
+        } else if NaiveDate::parse_from_str(&record.date, "%Y-%m-%d").is_err() {
+            problem(format!(
+                "The date \"{}\" is not in the YYYY-MM-DD format",
+                record.date
+            ));
+        }
+
+        let status = record.status_name(config);
+        if status.is_empty() {
+            problem(String::from("Has no status"));
+        } else if !known_statuses.contains(&status) {
+            problem(format!("The status \"{}\" is not recognised", status));
+        }
+
+        let this_path = record.relative_path(&config.record_path);
+        for relation in &record.relations {
+            let line = relation.render(record.format, config);
+            match by_path.get(&relation.target) {
+                None => problem(format!(
+                    "\"{}\" links to {}, which does not exist",
+                    line, relation.target
+                )),
+                Some(target) => {
+                    let reciprocal = relation.kind.reciprocal();
+                    let links_back = target
+                        .relations
+                        .iter()
+                        .any(|other| other.kind == reciprocal && other.target == this_path);
+                    if !links_back {
+                        problem(format!(
+                            "\"{}\" has no matching \"{}\" in {}",
+                            line,
+                            config
+                                .translate(reciprocal.template())
+                                .replace('#', "")
+                                .trim(),
+                            relation.target
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(problems)
+}
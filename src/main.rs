@@ -1,19 +1,41 @@
 use std::env;
-use std::io;
 use std::path::Path;
+use std::process;
 
-use clap::{Arg, ArgAction, Command};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use decision_record::generate::NavFormat;
+use decision_record::graph::{self, GraphFormat};
+use decision_record::json::{self, Json};
+use decision_record::{diff, export, generate, import, init, lint, repository};
+use decision_record::{Error, Format, NewRecord, Record, Repository, Status};
 use pathdiff::diff_paths;
 
-mod config;
-mod decision_record;
-mod init;
+// Run the command, and if it fails, say why and exit with the code for that error (see
+// `Error::exit_code`).
+fn main() {
+    let matches = cli().get_matches();
+    if let Err(error) = run(&matches) {
+        report_error(&matches, &error);
+        process::exit(error.exit_code());
+    }
+}
 
-fn main() -> Result<(), io::Error> {
-    let app = Command::new("decision-record")
+fn cli() -> Command {
+    Command::new("decision-record")
         .version("0.0.4")
         .author("Jon Spriggs <jon@sprig.gs>")
         .about("Making Decision Records easier. See https://github.com/DecisionRecords/ for more details.")
+        .arg(Arg::new("dry_run")
+            .help("Show the changes which would be made, as a diff, without writing anything.")
+            .long("dry-run")
+            .global(true)
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("output")
+            .help("How to print the result: text for people, or json for scripts.")
+            .long("output")
+            .global(true)
+            .value_parser(["text", "json"])
+            .default_value("text"))
         .subcommand(
             Command::new("init")
                 .about("Initializes the directory structures for new decision records.")
@@ -27,7 +49,7 @@ fn main() -> Result<(), io::Error> {
                     .short('t')
                     .default_value("template"))
                 .arg(Arg::new("format")
-                    .help("Set the Decision Record template format to use.")
+                    .help("Set the Decision Record template format to use: md, rst, adoc or madr.")
                     .long("format")
                     .short('f')
                     .default_value("md"))
@@ -55,6 +77,43 @@ fn main() -> Result<(), io::Error> {
                     .long("force")
                     .action(ArgAction::SetTrue)),
         )
+        .subcommand(
+            Command::new("migrate")
+                .about("Replace an .adr-dir (from init --adr) with a .decisionrecords-config for the same records, installing the templates init would.")
+                .arg(Arg::new("template_file")
+                    .help("Set the filename prefix for the Decision Record template to use.")
+                    .long("template")
+                    .short('t')
+                    .default_value("template"))
+                .arg(Arg::new("format")
+                    .help("Set the Decision Record template format to use: md, rst, adoc or madr. [default: the format of the existing records]")
+                    .long("format")
+                    .short('f')
+                    .num_args(1))
+                .arg(Arg::new("language")
+                    .help("The two or four-letter code defining the language to prefer.")
+                    .long("language")
+                    .short('l')
+                    .default_value("en"))
+                .arg(Arg::new("template_directory")
+                    .help("The template directory to use. [default: DOC_PATH/.template/]")
+                    .long("template-directory")
+                    .short('d')
+                    .num_args(1))
+                .arg(Arg::new("default_proposed")
+                    .help("Default new records as 'proposed' rather than 'accepted'.")
+                    .long("default-proposed")
+                    .short('p')
+                    .action(ArgAction::SetTrue))
+                .arg(Arg::new("remove_adr_dir")
+                    .help("Remove the .adr-dir afterwards, rather than keeping it for adr-tools.")
+                    .long("remove-adr-dir")
+                    .action(ArgAction::SetTrue))
+                .arg(Arg::new("force")
+                    .help("Force overwriting of an existing config.")
+                    .long("force")
+                    .action(ArgAction::SetTrue)),
+        )
         .subcommand(
             Command::new("new")
                 .about("Creates a new decision record.")
@@ -93,7 +152,9 @@ fn main() -> Result<(), io::Error> {
                     .long("approved")
                     .short('A')
                     .conflicts_with("proposed")
-                    .action(ArgAction::SetTrue)),
+                    .action(ArgAction::SetTrue))
+                .arg(date_arg())
+                .arg(earlier_date_arg()),
         )
         .subcommand(
           Command::new("approve")
@@ -105,6 +166,8 @@ fn main() -> Result<(), io::Error> {
                 .required(true)
                 .num_args(1..)
             )
+            .arg(date_arg())
+            .arg(earlier_date_arg())
         )
         .subcommand(
           Command::new("reject")
@@ -116,6 +179,8 @@ fn main() -> Result<(), io::Error> {
                 .required(true)
                 .num_args(1..)
             )
+            .arg(date_arg())
+            .arg(earlier_date_arg())
         )
         .subcommand(
           Command::new("proposed")
@@ -126,6 +191,43 @@ fn main() -> Result<(), io::Error> {
                 .required(true)
                 .num_args(1..)
             )
+            .arg(date_arg())
+            .arg(earlier_date_arg())
+        )
+        .subcommand(
+          Command::new("status")
+            .about("Change the status of a Decision Record to any status in the configured workflow.")
+            .arg(
+              Arg::new("record")
+                .help("The record to change the status of")
+                .required(true)
+            )
+            .arg(
+              Arg::new("status")
+                .help("The status to change to, e.g. \"In Review\"")
+                .required(true)
+                .num_args(1..)
+            )
+            .arg(date_arg())
+            .arg(earlier_date_arg())
+        )
+        .subcommand(
+          Command::new("show")
+            .about("Show a Decision Record.")
+            .arg(
+              Arg::new("record")
+                .help("The record to show")
+                .required(true)
+            )
+        )
+        .subcommand(
+          Command::new("history")
+            .about("Show every status change of a Decision Record, and its links to other records.")
+            .arg(
+              Arg::new("record")
+                .help("The record to show the history of")
+                .required(true)
+            )
         )
         .subcommand(
           Command::new("link")
@@ -159,6 +261,8 @@ fn main() -> Result<(), io::Error> {
                 .help("Link to a record")
                 .required(true)
             )
+            .arg(date_arg())
+            .arg(earlier_date_arg())
         )
         .subcommand(
           Command::new("amend")
@@ -188,11 +292,120 @@ fn main() -> Result<(), io::Error> {
                 .help("Link to a record")
                 .required(true)
             )
+            .arg(date_arg())
+            .arg(earlier_date_arg())
         )
-        ;
-
-    let matches = app.get_matches();
+        .subcommand(
+          Command::new("retitle")
+            .about("Change the title of a Decision Record, renaming the file and updating the links to it.")
+            .arg(
+              Arg::new("record")
+                .help("The record to change the title of")
+                .required(true)
+            )
+            .arg(
+              Arg::new("title")
+                .help("The new title of the record")
+                .required(true)
+                .num_args(1..)
+            )
+        )
+        .subcommand(
+          Command::new("renumber")
+            .about("Give new numbers to Decision Records which share a number, e.g. after merging two branches.")
+        )
+        .subcommand(
+          Command::new("convert")
+            .about("Convert Decision Records between Markdown and Restructured Text, renaming the files and updating the links to them. With no records given, every record is converted and the fileType in .decisionrecords-config is changed to match.")
+            .arg(Arg::new("to")
+              .help("The format to convert the records to.")
+              .long("to")
+              .required(true)
+              .value_parser(["md", "rst"]))
+            .arg(Arg::new("records")
+              .help("The records to convert [default: every record]")
+              .num_args(0..))
+        )
+        .subcommand(
+          Command::new("generate")
+            .about("Generate documents from the decision records.")
+            .subcommand_required(true)
+            .subcommand(
+              Command::new("toc")
+                .about("Generate an index of the decision records, as a Markdown list or a Restructured Text toctree.")
+                .arg(Arg::new("by_status")
+                  .help("Group the records under a heading for each status.")
+                  .long("by-status")
+                  .short('s')
+                  .action(ArgAction::SetTrue))
+                .arg(Arg::new("readme")
+                  .help("Replace the text between the decision-records-toc start and end markers in this file, instead of printing the index.")
+                  .long("readme")
+                  .short('r')
+                  .num_args(1))
+            )
+            .subcommand(
+              Command::new("nav")
+                .about("Generate the navigation for an mdBook (SUMMARY.md), MkDocs (mkdocs.yml) or Sphinx (toctree) documentation site.")
+                .arg(Arg::new("for")
+                  .help("The documentation site generator to make the navigation for.")
+                  .long("for")
+                  .required(true)
+                  .value_parser(["mdbook", "mkdocs", "sphinx"]))
+                .arg(Arg::new("base")
+                  .help("The directory the site is built from, which the links are relative to, e.g. the mdBook src directory or the MkDocs docs_dir. [default: the current directory]")
+                  .long("base")
+                  .short('b')
+                  .num_args(1))
+                .arg(Arg::new("file")
+                  .help("Replace the text between the decision-records-toc start and end markers in this file, instead of printing the navigation.")
+                  .long("file")
+                  .num_args(1))
+            )
+        )
+        .subcommand(
+          Command::new("import")
+            .about("Import decision records written by other tools.")
+            .subcommand_required(true)
+            .subcommand(
+              Command::new("adr-tools")
+                .about("Convert the records written by adr-tools (in .adr-dir or doc/adr) into this tool's statuses and links, and write a .decisionrecords-config for them.")
+            )
+        )
+        .subcommand(
+          Command::new("export")
+            .about("Export the decision records to other forms.")
+            .subcommand_required(true)
+            .subcommand(
+              Command::new("html")
+                .about("Export the decision records as a static HTML site, with an index grouped by status and a search box.")
+                .arg(Arg::new("output_directory")
+                  .help("The directory to write the site into.")
+                  .required(true))
+            )
+        )
+        .subcommand(
+          Command::new("graph")
+            .about("Show the links between the decision records as a Graphviz DOT or Mermaid graph.")
+            .arg(Arg::new("format")
+              .help("The graph language to use.")
+              .long("format")
+              .short('f')
+              .value_parser(["dot", "mermaid"])
+              .default_value("dot"))
+        )
+        .subcommand(
+          Command::new("lint")
+            .about("Check the decision records for missing headings, bad dates, unknown statuses and broken links. Exits non-zero if any problems are found.")
+        )
+        .subcommand(
+          Command::new("list")
+            .about("List the decision records, with their date and current status.")
+            .visible_alias("ls")
+        )
+}
 
+fn run(matches: &ArgMatches) -> Result<(), Error> {
     match matches.subcommand() {
         Some(("init", submatch)) => {
             let root_dir = env::current_dir()?;
@@ -213,7 +426,7 @@ fn main() -> Result<(), io::Error> {
             let absolute_doc_path = Path::new(&str_doc_path);
 
             let relative_doc_path = diff_paths(absolute_doc_path, absolute_root_dir)
-                .unwrap()
+                .unwrap_or_else(|| absolute_doc_path.to_path_buf())
                 .display()
                 .to_string();
 
@@ -241,6 +454,12 @@ fn main() -> Result<(), io::Error> {
 
             let default_proposed = submatch.get_flag("default_proposed");
 
+            if submatch.get_flag("dry_run") {
+                return Err(Error::InvalidInput(String::from(
+                    "init can't be run with --dry-run.",
+                )));
+            }
+
             if adr_format {
                 init::short_init(root_dir, doc_path, force)?;
             } else {
@@ -264,79 +483,99 @@ fn main() -> Result<(), io::Error> {
                 .collect::<Vec<_>>()
                 .join(" ");
 
-            let supersede = submatch
-                .get_many::<String>("supersede")
-                .map(|vals| vals.map(|s| s.to_string()).collect::<Vec<_>>().join(","))
-                .unwrap_or_default();
-
-            let deprecate = submatch
-                .get_many::<String>("deprecate")
-                .map(|vals| vals.map(|s| s.to_string()).collect::<Vec<_>>().join(","))
-                .unwrap_or_default();
-
-            let amend = submatch
-                .get_many::<String>("amend")
-                .map(|vals| vals.map(|s| s.to_string()).collect::<Vec<_>>().join(","))
-                .unwrap_or_default();
-
-            let link = submatch
-                .get_many::<String>("link")
-                .map(|vals| vals.map(|s| s.to_string()).collect::<Vec<_>>().join(","))
-                .unwrap_or_default();
-
-            let proposed = submatch.get_flag("proposed");
-            let approved = submatch.get_flag("approved");
+            let mut status = None;
+            if submatch.get_flag("proposed") {
+                status = Some(Status::Proposed);
+            } else if submatch.get_flag("approved") {
+                status = Some(Status::Approved);
+            }
 
-            decision_record::new_record(
+            let new_record = NewRecord {
                 title,
-                supersede,
-                deprecate,
-                amend,
-                link,
-                proposed,
-                approved,
-                config::load_config().unwrap(),
-            )?;
+                status,
+                supersedes: record_list(submatch, "supersede"),
+                deprecates: record_list(submatch, "deprecate"),
+                amends: record_list(submatch, "amend"),
+                links: record_list(submatch, "link"),
+            };
+
+            let repository = open_dated_repository(submatch)?;
+            let record = repository.new_record(&new_record)?;
+            if is_json(submatch) {
+                print_json(&json::record(
+                    &record,
+                    repository.config(),
+                    &env::current_dir()?,
+                ));
+            } else {
+                println!(
+                    "{} file {}",
+                    done(&repository, "Created", "create"),
+                    record.relative_path(&repository.config().record_path)
+                );
+            }
+            show_dry_run(submatch, &repository)?;
         }
         Some(("approve", submatch)) => {
-            let mut records: String = "".to_owned();
-            if let Some(record_items) = submatch.get_many::<String>("record") {
-                for record_item in record_items {
-                    if !records.is_empty() {
-                        records.push(',');
-                    }
-                    records.push_str(record_item);
-                }
-            }
-            decision_record::approve(records)?;
+            change_status(submatch, Status::Approved, "approved")?;
         }
         Some(("reject", submatch)) => {
-            let mut records: String = "".to_owned();
-            if let Some(record_items) = submatch.get_many::<String>("record") {
-                for record_item in record_items {
-                    if !records.is_empty() {
-                        records.push(',');
-                    }
-                    records.push_str(record_item);
-                }
-            }
-            decision_record::reject(records)?;
+            change_status(submatch, Status::Rejected, "rejected")?;
         }
         Some(("proposed", submatch)) => {
-            let mut records: String = "".to_owned();
-            if let Some(record_items) = submatch.get_many::<String>("record") {
-                for record_item in record_items {
-                    if !records.is_empty() {
-                        records.push(',');
-                    }
-                    records.push_str(record_item);
-                }
+            change_status(submatch, Status::Proposed, "proposed")?;
+        }
+        Some(("status", submatch)) => {
+            let record = submatch.get_one::<String>("record").unwrap();
+            let status: String = submatch
+                .get_many::<String>("status")
+                .unwrap()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            let repository = open_dated_repository(submatch)?;
+            let changed = repository.change_status(record, &status)?;
+            if is_json(submatch) {
+                print_records(&repository, &[changed])?;
+            } else {
+                println!(
+                    "Record {} {} {}.",
+                    changed.identifier(),
+                    if repository.is_dry_run() {
+                        "would be"
+                    } else {
+                        "is now"
+                    },
+                    changed.status_name(repository.config())
+                );
+            }
+            show_dry_run(submatch, &repository)?;
+        }
+        Some(("show", submatch)) => {
+            let repository = open_repository(submatch)?;
+            let record = repository.find(submatch.get_one::<String>("record").unwrap())?;
+            if is_json(submatch) {
+                print_json(&json::record_with_sections(
+                    &record,
+                    repository.config(),
+                    &env::current_dir()?,
+                ));
+            } else {
+                print!("{}", record.render(repository.config()));
+            }
+        }
+        Some(("history", submatch)) => {
+            let repository = open_repository(submatch)?;
+            let record = repository.find(submatch.get_one::<String>("record").unwrap())?;
+            println!("{}", record.link_text());
+            for line in record.history(repository.config()) {
+                println!("  {}", line);
             }
-            decision_record::proposed(records)?;
         }
         Some(("link", submatch)) => {
-            let from_record = submatch.get_one::<String>("from").unwrap().to_string();
-            let to_record = submatch.get_one::<String>("to").unwrap().to_string();
+            let from_records = record_list(submatch, "from");
+            let to_record = submatch.get_one::<String>("to").unwrap();
 
             let mut reason = String::new();
             if let Some(reason_items) = submatch.get_many::<String>("reason") {
@@ -347,28 +586,477 @@ fn main() -> Result<(), io::Error> {
                     reason.push_str(reason_item);
                 }
             }
-            decision_record::link(from_record, to_record, reason)?;
+            let reason = if reason.is_empty() {
+                None
+            } else {
+                Some(reason.as_str())
+            };
+            let repository = open_repository(submatch)?;
+            let changed = repository.link(&as_strs(&from_records), to_record, reason)?;
+            print_changed(submatch, &repository, &changed)?;
+            show_dry_run(submatch, &repository)?;
         }
         Some(("deprecate", submatch)) => {
-            let from_record = submatch.get_one::<String>("from").unwrap().to_string();
-            let to_record = submatch.get_one::<String>("to").unwrap().to_string();
+            let from_records = record_list(submatch, "from");
+            let to_record = submatch.get_one::<String>("to").unwrap();
 
-            decision_record::deprecate(from_record, to_record)?;
+            let repository = open_dated_repository(submatch)?;
+            let changed = repository.deprecate(&as_strs(&from_records), to_record)?;
+            print_changed(submatch, &repository, &changed)?;
+            show_dry_run(submatch, &repository)?;
         }
         Some(("amend", submatch)) => {
-            let from_record = submatch.get_one::<String>("from").unwrap().to_string();
-            let to_record = submatch.get_one::<String>("to").unwrap().to_string();
+            let from_records = record_list(submatch, "from");
+            let to_record = submatch.get_one::<String>("to").unwrap();
 
-            decision_record::amend(from_record, to_record)?;
+            let repository = open_repository(submatch)?;
+            let changed = repository.amend(&as_strs(&from_records), to_record)?;
+            print_changed(submatch, &repository, &changed)?;
+            show_dry_run(submatch, &repository)?;
         }
         Some(("supersede", submatch)) => {
-            let from_record = submatch.get_one::<String>("from").unwrap().to_string();
-            let to_record = submatch.get_one::<String>("to").unwrap().to_string();
+            let from_records = record_list(submatch, "from");
+            let to_record = submatch.get_one::<String>("to").unwrap();
+
+            let repository = open_dated_repository(submatch)?;
+            let changed = repository.supersede(&as_strs(&from_records), to_record)?;
+            print_changed(submatch, &repository, &changed)?;
+            show_dry_run(submatch, &repository)?;
+        }
+        Some(("retitle", submatch)) => {
+            let record = submatch.get_one::<String>("record").unwrap();
+            let title: String = submatch
+                .get_many::<String>("title")
+                .unwrap()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>()
+                .join(" ");
 
-            decision_record::supersede(from_record, to_record)?;
+            let repository = open_repository(submatch)?;
+            let changed = repository.retitle(record, &title)?;
+            if is_json(submatch) {
+                print_records(&repository, &changed)?;
+            } else {
+                let record_path = &repository.config().record_path;
+                println!(
+                    "{} to {}",
+                    done(&repository, "Renamed", "rename"),
+                    changed[0].relative_path(record_path)
+                );
+                for other in &changed[1..] {
+                    println!(
+                        "{} links in {}",
+                        done(&repository, "Updated", "update"),
+                        other.relative_path(record_path)
+                    );
+                }
+            }
+            show_dry_run(submatch, &repository)?;
+        }
+        Some(("renumber", submatch)) => {
+            let repository = open_repository(submatch)?;
+            let renumbered = repository.renumber()?;
+            for (old_path, record) in &renumbered {
+                println!(
+                    "{} {} to {}",
+                    done(&repository, "Renumbered", "renumber"),
+                    old_path,
+                    record.relative_path(&repository.config().record_path)
+                );
+            }
+            if renumbered.is_empty() {
+                println!("No duplicate numbers found.");
+            }
+            show_dry_run(submatch, &repository)?;
+        }
+        Some(("convert", submatch)) => {
+            let to = submatch.get_one::<String>("to").unwrap();
+            let format = Format::from_extension(to).unwrap_or(Format::Markdown);
+            let records = record_list(submatch, "records");
+            let repository = open_repository(submatch)?;
+            let converted = repository.convert(&as_strs(&records), format)?;
+            if is_json(submatch) {
+                let records: Vec<Record> =
+                    converted.iter().map(|(_, record)| record.clone()).collect();
+                print_records(&repository, &records)?;
+            } else {
+                for (old_path, record) in &converted {
+                    println!(
+                        "{} {} to {}",
+                        done(&repository, "Converted", "convert"),
+                        old_path,
+                        record.relative_path(&repository.config().record_path)
+                    );
+                }
+                if converted.is_empty() {
+                    println!("Nothing to convert.");
+                }
+                if records.is_empty()
+                    && repository.config().config_file.is_none()
+                    && repository.config().template_format != *to
+                {
+                    println!(
+                        "There's no .decisionrecords-config, so new records will still be written as {}.",
+                        repository.config().template_format
+                    );
+                }
+            }
+            show_dry_run(submatch, &repository)?;
+        }
+        Some(("migrate", submatch)) => {
+            let migration = init::Migration {
+                template_file: submatch
+                    .get_one::<String>("template_file")
+                    .cloned()
+                    .unwrap_or_default(),
+                format: submatch
+                    .get_one::<String>("format")
+                    .cloned()
+                    .unwrap_or_default(),
+                language: submatch
+                    .get_one::<String>("language")
+                    .cloned()
+                    .unwrap_or_default(),
+                template_directory: submatch.get_one::<String>("template_directory").cloned(),
+                default_proposed: submatch.get_flag("default_proposed"),
+                remove_adr_dir: submatch.get_flag("remove_adr_dir"),
+                force: submatch.get_flag("force"),
+            };
+            let repository = open_repository(submatch)?;
+            let current_dir = env::current_dir()?;
+            let written = init::migrate(&repository, &current_dir, &migration)?;
+            for path in &written {
+                let path = diff_paths(path, &current_dir).unwrap_or_else(|| path.clone());
+                println!("{} {}", done(&repository, "Wrote", "write"), path.display());
+            }
+            if migration.remove_adr_dir {
+                println!("{} .adr-dir", done(&repository, "Removed", "remove"));
+            } else {
+                println!(
+                    "{} .adr-dir, which is only read by adr-tools from now on",
+                    done(&repository, "Kept", "keep")
+                );
+            }
+            show_dry_run(submatch, &repository)?;
+        }
+        Some(("generate", submatch)) => match submatch.subcommand() {
+            Some(("toc", tocmatch)) => {
+                let repository = open_repository(tocmatch)?;
+                let by_status = tocmatch.get_flag("by_status");
+                if let Some(readme) = tocmatch.get_one::<String>("readme") {
+                    let readme_path = env::current_dir()?.join(readme);
+                    let base_path = readme_path.parent().unwrap_or(Path::new(""));
+                    let format = Format::from_path(&readme_path)
+                        .or_else(|| Format::from_extension(&repository.config().template_format))
+                        .unwrap_or(Format::Markdown);
+                    let toc = generate::toc(&repository, format, base_path, by_status, false)?;
+                    generate::write_between_markers(&repository, &readme_path, &toc)?;
+                    println!("{} {}", done(&repository, "Updated", "update"), readme);
+                    show_dry_run(tocmatch, &repository)?;
+                } else {
+                    let format = Format::from_extension(&repository.config().template_format)
+                        .unwrap_or(Format::Markdown);
+                    let base_path = repository.config().record_path.clone();
+                    print!(
+                        "{}",
+                        generate::toc(&repository, format, &base_path, by_status, true)?
+                    );
+                }
+            }
+            Some(("nav", navmatch)) => {
+                let repository = open_repository(navmatch)?;
+                let format = navmatch
+                    .get_one::<String>("for")
+                    .and_then(|name| NavFormat::from_name(name))
+                    .unwrap_or(NavFormat::MdBook);
+                let current_dir = env::current_dir()?;
+                let base_path = match navmatch.get_one::<String>("base") {
+                    Some(base) => current_dir.join(base),
+                    None => current_dir.clone(),
+                };
+                let nav = generate::nav(&repository, format, &base_path)?;
+                if let Some(file) = navmatch.get_one::<String>("file") {
+                    generate::write_between_markers(&repository, &current_dir.join(file), &nav)?;
+                    println!("{} {}", done(&repository, "Updated", "update"), file);
+                    show_dry_run(navmatch, &repository)?;
+                } else {
+                    print!("{}", nav);
+                }
+            }
+            _ => {
+                println!("decision-record generate command not recognized. Use --help for options.")
+            }
+        },
+        Some(("import", submatch)) => match submatch.subcommand() {
+            Some(("adr-tools", importmatch)) => {
+                let repository = open_repository(importmatch)?;
+                let current_dir = env::current_dir()?;
+                let imported = import::adr_tools(&repository, &current_dir)?;
+                let relative =
+                    |path: &Path| diff_paths(path, &current_dir).unwrap_or(path.to_path_buf());
+                for path in &imported.converted {
+                    println!(
+                        "{} {}",
+                        done(&repository, "Converted", "convert"),
+                        relative(path).display()
+                    );
+                }
+                match &imported.config_file {
+                    Some(path) => println!(
+                        "{} {}",
+                        done(&repository, "Created", "create"),
+                        relative(path).display()
+                    ),
+                    None => println!(
+                        "{} the existing .decisionrecords-config",
+                        done(&repository, "Kept", "keep")
+                    ),
+                }
+                for problem in &imported.problems {
+                    println!("{}: {}", relative(&problem.path).display(), problem.message);
+                }
+                if !imported.problems.is_empty() {
+                    eprintln!(
+                        "{} thing(s) couldn't be fully converted; see above.",
+                        imported.problems.len()
+                    );
+                }
+                show_dry_run(importmatch, &repository)?;
+            }
+            _ => {
+                println!("decision-record import command not recognized. Use --help for options.")
+            }
+        },
+        Some(("export", submatch)) => match submatch.subcommand() {
+            Some(("html", htmlmatch)) => {
+                let repository = open_repository(htmlmatch)?;
+                let output_directory = env::current_dir()?
+                    .join(htmlmatch.get_one::<String>("output_directory").unwrap());
+                let written = export::html(&repository, &output_directory)?;
+                println!(
+                    "{} {} page(s) to {}",
+                    done(&repository, "Exported", "export"),
+                    written.len(),
+                    output_directory.display()
+                );
+                show_dry_run(htmlmatch, &repository)?;
+            }
+            _ => {
+                println!("decision-record export command not recognized. Use --help for options.")
+            }
+        },
+        Some(("graph", submatch)) => {
+            let format = submatch
+                .get_one::<String>("format")
+                .and_then(|name| GraphFormat::from_name(name))
+                .unwrap_or(GraphFormat::Dot);
+            print!("{}", graph::graph(&open_repository(submatch)?, format)?);
+        }
+        Some(("lint", submatch)) => {
+            let repository = open_repository(submatch)?;
+            let problems = lint::lint(&repository)?;
+            let current_dir = env::current_dir()?;
+            for problem in &problems {
+                let path = diff_paths(&problem.path, &current_dir).unwrap_or(problem.path.clone());
+                println!("{}: {}", path.display(), problem.message);
+            }
+            if !problems.is_empty() {
+                eprintln!("Found {} problem(s).", problems.len());
+                process::exit(1);
+            }
+            println!("No problems found.");
+        }
+        Some(("list", submatch)) => {
+            let repository = open_repository(submatch)?;
+            if is_json(submatch) {
+                return print_records(&repository, &repository.records()?);
+            }
+            let mut rows: Vec<Vec<String>> = vec![vec![
+                String::from("Number"),
+                String::from("Title"),
+                String::from("Date"),
+                String::from("Status"),
+            ]];
+            for record in repository.records()? {
+                rows.push(vec![
+                    record.identifier(),
+                    record.title.clone(),
+                    record.date.clone(),
+                    record.current_status(repository.config()),
+                ]);
+            }
+            print_table(&rows);
         }
         _ => println!("decision-record command not recognized. Use --help for options."),
     }
 
     Ok(())
 }
+
+// Open the repository in the current directory, only keeping changes in memory with --dry-run.
+fn open_repository(submatch: &ArgMatches) -> Result<Repository, Error> {
+    Ok(Repository::open(&env::current_dir()?)?.with_dry_run(submatch.get_flag("dry_run")))
+}
+
+// Open the repository, using the date given with --date (if any) for the changes made.
+fn open_dated_repository(submatch: &ArgMatches) -> Result<Repository, Error> {
+    let repository =
+        open_repository(submatch)?.with_earlier_dates(submatch.get_flag("earlier_date"));
+    match submatch.get_one::<String>("date") {
+        Some(date) => Ok(repository.with_date(repository::parse_date(date)?)),
+        None => Ok(repository),
+    }
+}
+
+// The --date option, for the commands which write a date into a record.
+fn date_arg() -> Arg {
+    Arg::new("date")
+        .help("The date to record, as YYYY-MM-DD, rather than today.")
+        .long("date")
+        .num_args(1)
+}
+
+// The --allow-earlier-date option, for back-filling the history of a record out of order.
+fn earlier_date_arg() -> Arg {
+    Arg::new("earlier_date")
+        .help("Allow the date to be before the record's latest status change.")
+        .long("allow-earlier-date")
+        .action(ArgAction::SetTrue)
+}
+
+// Collect the values given for an argument, splitting any comma separated lists of records, so
+// that `1 2`, `1,2` and `1, 2` all give the same list.
+fn record_list(submatch: &ArgMatches, argument: &str) -> Vec<String> {
+    let mut records: Vec<String> = Vec::new();
+    if let Some(values) = submatch.get_many::<String>(argument) {
+        for value in values {
+            for record in value.split_terminator(',') {
+                if !record.trim().is_empty() {
+                    records.push(record.trim().to_string());
+                }
+            }
+        }
+    }
+    records
+}
+
+fn as_strs(values: &[String]) -> Vec<&str> {
+    values.iter().map(String::as_str).collect()
+}
+
+// Change the status of each record given, or (if any of them can't be changed, e.g. it can't be
+// found) none of them.
+fn change_status(submatch: &ArgMatches, status: Status, action: &str) -> Result<(), Error> {
+    let repository = open_dated_repository(submatch)?;
+    let records = record_list(submatch, "record");
+    let changed = repository.set_statuses(&as_strs(&records), status)?;
+    if is_json(submatch) {
+        print_records(&repository, &changed)?;
+    } else {
+        for record in &changed {
+            if repository.is_dry_run() {
+                println!("Record {} would be {}.", record.identifier(), action);
+            } else {
+                println!("Record {} {}.", record.identifier(), action);
+            }
+        }
+    }
+    show_dry_run(submatch, &repository)?;
+    Ok(())
+}
+
+// Whether the result should be printed as JSON (--output json) rather than text.
+fn is_json(submatch: &ArgMatches) -> bool {
+    submatch
+        .get_one::<String>("output")
+        .is_some_and(|output| output == "json")
+}
+
+fn print_json(json: &Json) {
+    println!("{}", json.render());
+}
+
+// Print the records as a JSON array, in the shape described in `json::record`.
+fn print_records(repository: &Repository, records: &[Record]) -> Result<(), Error> {
+    let current_dir = env::current_dir()?;
+    print_json(&Json::Array(
+        records
+            .iter()
+            .map(|record| json::record(record, repository.config(), &current_dir))
+            .collect(),
+    ));
+    Ok(())
+}
+
+// The records changed by linking them, which are only printed as JSON; the text output is silent.
+fn print_changed(
+    submatch: &ArgMatches,
+    repository: &Repository,
+    changed: &[Record],
+) -> Result<(), Error> {
+    if is_json(submatch) {
+        print_records(repository, changed)?;
+    }
+    Ok(())
+}
+
+fn report_error(submatch: &ArgMatches, error: &Error) {
+    if is_json(submatch) {
+        eprintln!("{}", json::error(error).render());
+    } else {
+        eprintln!("Error: {}", error);
+    }
+}
+
+// What the command did to a file, e.g. "Created", or in a dry run what it would have done, e.g.
+// "Would create".
+fn done(repository: &Repository, past: &str, present: &str) -> String {
+    if repository.is_dry_run() {
+        format!("Would {}", present)
+    } else {
+        past.to_string()
+    }
+}
+
+// In a dry run, print the changes which would have been made to each file as a unified diff. With
+// --output json, only the records (as they would be) are printed, so the output stays JSON.
+fn show_dry_run(submatch: &ArgMatches, repository: &Repository) -> Result<(), Error> {
+    if !repository.is_dry_run() || is_json(submatch) {
+        return Ok(());
+    }
+    let current_dir = env::current_dir()?;
+    for change in repository.changes()? {
+        let path = diff_paths(&change.path, &current_dir).unwrap_or(change.path.clone());
+        print!(
+            "{}",
+            diff::unified_diff(
+                &path.display().to_string(),
+                change.before.as_deref(),
+                change.after.as_deref()
+            )
+        );
+    }
+    Ok(())
+}
+
+// Print the rows as a table, with the columns lined up. The first row is the heading.
+fn print_table(rows: &[Vec<String>]) {
+    let mut widths: Vec<usize> = Vec::new();
+    for row in rows {
+        for (column, value) in row.iter().enumerate() {
+            if widths.len() <= column {
+                widths.push(0);
+            }
+            widths[column] = widths[column].max(value.chars().count());
+        }
+    }
+
+    for row in rows {
+        let line: Vec<String> = row
+            .iter()
+            .enumerate()
+            .map(|(column, value)| format!("{:<width$}", value, width = widths[column]))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    }
+}
@@ -0,0 +1,155 @@
+// The inline and block markup which both exporting records (as HTML) and converting them (between
+// Markdown and Restructured Text) need to find, so that the two read the records in the same way.
+
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use std::path::Path;
+
+lazy_static! {
+    // Inline code in Markdown (and AsciiDoc), and in Restructured Text.
+    pub(crate) static ref RE_CODE: Regex = Regex::new(r"`([^`]+)`").unwrap();
+    pub(crate) static ref RE_RST_CODE: Regex = Regex::new(r"``([^`]+)``").unwrap();
+    // A Markdown link or image, capturing the `!` of an image, the label and the target.
+    pub(crate) static ref RE_MARKDOWN_LINK: Regex =
+        Regex::new(r#"(!?)\[([^\]]*)\]\(([^)\s]+)(?:\s+"[^"]*")?\)"#).unwrap();
+    // A web address in angle brackets, which Markdown shows as a link.
+    pub(crate) static ref RE_AUTOLINK: Regex = Regex::new(r"<(https?://[^>\s]+)>").unwrap();
+    // A Restructured Text link to another document, with or without a label.
+    pub(crate) static ref RE_DOC_LINK: Regex =
+        Regex::new(r":doc:`(?:([^`<]*?)\s*<([^>]+)>|([^`<]+))`").unwrap();
+    // Any other Restructured Text link, capturing the label and the target.
+    pub(crate) static ref RE_RST_LINK: Regex = Regex::new(r"`([^`<]*?)\s*<([^>]+)>`__?").unwrap();
+    // The punctuation under a Restructured Text heading inside a section.
+    pub(crate) static ref RE_UNDERLINE: Regex =
+        Regex::new(r"^(=+|-+|~+|\^+|\x22+|'+|`+|#+|\*+|\++)\s*$").unwrap();
+    // A Restructured Text code block directive, capturing its indent and language.
+    pub(crate) static ref RE_CODE_DIRECTIVE: Regex =
+        Regex::new(r"^(\s*)\.\.\s+(?:code-block|code|sourcecode)::\s*(\S*)\s*$").unwrap();
+    // A Markdown code fence, capturing its indent, the fence and the language.
+    pub(crate) static ref RE_FENCE: Regex = Regex::new(r"^(\s*)(```|~~~)\s*([\w+#.-]*)").unwrap();
+    // A list item (bulleted or numbered), capturing its indent, marker and text.
+    pub(crate) static ref RE_MARKDOWN_ITEM: Regex =
+        Regex::new(r"^(\s*)([*+-]|\d+[.)])\s+(.*)$").unwrap();
+    pub(crate) static ref RE_RST_ITEM: Regex =
+        Regex::new(r"^(\s*)([*+-]|\d+\.|#\.)\s+(.*)$").unwrap();
+    // A Restructured Text comment (or other explicit markup), capturing its indent and text.
+    pub(crate) static ref RE_RST_COMMENT: Regex = Regex::new(r"^(\s*)\.\.(?:\s+(.*))?$").unwrap();
+    // Bold and italic text, with stars or underscores.
+    pub(crate) static ref RE_DOUBLE_STAR: Regex = Regex::new(r"\*\*(.+?)\*\*").unwrap();
+    pub(crate) static ref RE_DOUBLE_UNDERSCORE: Regex = Regex::new(r"__(.+?)__").unwrap();
+    pub(crate) static ref RE_STAR: Regex = Regex::new(r"\*([^*\s][^*]*?)\*").unwrap();
+    pub(crate) static ref RE_UNDERSCORE: Regex = Regex::new(r"\b_([^_]+?)_\b").unwrap();
+    static ref RE_PLACEHOLDER: Regex = Regex::new(r"\x01(\d+)\x01").unwrap();
+}
+
+// Turns a piece of inline markup (e.g. a link) into its replacement.
+pub(crate) type Replacement<'a> = Box<dyn Fn(&Captures) -> String + 'a>;
+
+// Replace the inline markup found by each pattern, in order, then change the rest of the text with
+// `rest`. Each replacement is swapped for a placeholder until the end, so nothing inside code or
+// links is changed by a later pattern, or by `rest` (e.g. when it turns `*text*` into emphasis).
+pub(crate) fn replace_inline(
+    text: &str,
+    replacements: &[(&Regex, Replacement)],
+    rest: impl FnOnce(&str) -> String,
+) -> String {
+    let mut fragments: Vec<String> = Vec::new();
+    let mut text = text.replace('\u{0001}', "");
+    for (pattern, replace) in replacements {
+        text = pattern
+            .replace_all(&text, |captures: &Captures| {
+                fragments.push(replace(captures));
+                format!("\u{0001}{}\u{0001}", fragments.len() - 1)
+            })
+            .to_string();
+    }
+    RE_PLACEHOLDER
+        .replace_all(&rest(&text), |captures: &Captures| {
+            captures[1]
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| fragments.get(index))
+                .cloned()
+                .unwrap_or_default()
+        })
+        .to_string()
+}
+
+// The label and the file of a link found by `RE_DOC_LINK`. A link without a label is labelled
+// with the document, and a document without an extension is a Restructured Text file.
+pub(crate) fn doc_link(captures: &Captures) -> (String, String) {
+    let document = captures
+        .get(2)
+        .or_else(|| captures.get(3))
+        .map_or("", |document| document.as_str().trim());
+    let label = match captures.get(1) {
+        Some(label) if !label.as_str().is_empty() => label.as_str(),
+        _ => document,
+    };
+    let file = if Path::new(document).extension().is_some() {
+        document.to_string()
+    } else {
+        format!("{}.rst", document)
+    };
+    (label.to_string(), file)
+}
+
+// Whether a line of Restructured Text starts a literal block ("Like this::"), with the text as it
+// reads: "Like this:", or nothing for a lone "::".
+pub(crate) fn literal_marker(text: &str) -> (bool, &str) {
+    let trimmed = text.trim_end();
+    match trimmed.strip_suffix("::") {
+        Some(before) if before.trim().is_empty() => (true, ""),
+        Some(_) => (true, &trimmed[..trimmed.len() - 1]),
+        None => (false, text),
+    }
+}
+
+// The Restructured Text literal block (or code block directive, when `directive` is set) at
+// `index`: the lines indented by more than `indent`, without the blank lines at either end or the
+// indent they share. `index` is moved past the block, and the flag says whether it ended with
+// blank lines.
+pub(crate) fn literal_block(
+    lines: &[String],
+    index: &mut usize,
+    indent: usize,
+    directive: bool,
+) -> (Vec<String>, bool) {
+    if directive {
+        // Skip the directive and its options.
+        *index += 1;
+        while *index < lines.len() && lines[*index].trim().starts_with(':') {
+            *index += 1;
+        }
+    }
+    let start = *index;
+    while *index < lines.len()
+        && (lines[*index].trim().is_empty() || indent_of(&lines[*index]) > indent)
+    {
+        *index += 1;
+    }
+    let mut code: Vec<&str> = lines[start..*index].iter().map(String::as_str).collect();
+    while code.first().is_some_and(|line| line.trim().is_empty()) {
+        code.remove(0);
+    }
+    let mut trailing_blank = false;
+    while code.last().is_some_and(|line| line.trim().is_empty()) {
+        code.pop();
+        trailing_blank = true;
+    }
+    let code_indent = code
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| indent_of(line))
+        .min()
+        .unwrap_or(0);
+    let code = code
+        .iter()
+        .map(|line| line.chars().skip(code_indent).collect())
+        .collect();
+    (code, trailing_blank)
+}
+
+pub(crate) fn indent_of(line: &str) -> usize {
+    line.chars().take_while(|c| c.is_whitespace()).count()
+}
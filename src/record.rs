@@ -0,0 +1,1173 @@
+use lazy_static::lazy_static;
+use pathdiff::diff_paths;
+use regex::Regex;
+use std::cmp::Ordering;
+use std::fs::{read_dir, read_to_string, File};
+use std::io::prelude::*;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::config::Config;
+use crate::front_matter::{self, FrontMatter, Value};
+
+lazy_static! {
+    // A "Date: 2020-01-01" line, capturing everything up to the date.
+    static ref RE_DATE_LINE: Regex = Regex::new(r"^(\s*Date:\s*)(.*?)\s*$").unwrap();
+    static ref RE_DATE: Regex = Regex::new(r"\d{4}-\d{2}-\d{2}").unwrap();
+    static ref RE_MARKDOWN_HEADING: Regex =
+        Regex::new(r"^(#{1,6})\s+(.*?)(?:\s+#+)?\s*$").unwrap();
+    static ref RE_MARKDOWN_FENCE: Regex = Regex::new(r"^ {0,3}(`{3,}|~{3,})").unwrap();
+    static ref RE_TITLE_RULE: Regex = Regex::new(r"^\s*#+\s*$").unwrap();
+    static ref RE_HEADING_RULE: Regex = Regex::new(r"^\s*\*+\s*$").unwrap();
+    static ref RE_ASCIIDOC_HEADING: Regex = Regex::new(r"^(=+)\s+(.*?)\s*$").unwrap();
+    static ref RE_ASCIIDOC_DELIMITER: Regex = Regex::new(r"^(-{4,}|\.{4,}|```)\s*$").unwrap();
+    static ref RE_NUMBERED_TITLE: Regex = Regex::new(r"^\s*(\d+)\.?\s+(.*?)\s*$").unwrap();
+    static ref RE_NUMBERED_FILENAME: Regex = Regex::new(r"^(\d{4,})-(.*)$").unwrap();
+}
+
+// The file formats a record can be written in. The format is worked out from the extension of the
+// record, falling back to the `fileType` in the config.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Format {
+    Markdown,
+    ReStructuredText,
+    AsciiDoc,
+}
+
+impl Format {
+    pub fn from_extension(extension: &str) -> Option<Format> {
+        match extension {
+            "md" => Some(Format::Markdown),
+            "rst" => Some(Format::ReStructuredText),
+            "adoc" => Some(Format::AsciiDoc),
+            _ => None,
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Format> {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(Format::from_extension)
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Markdown => "md",
+            Format::ReStructuredText => "rst",
+            Format::AsciiDoc => "adoc",
+        }
+    }
+
+    // Produce a link to another record, in the style this format uses.
+    pub fn link(&self, text: &str, target: &str) -> String {
+        match self {
+            Format::Markdown => format!("[{}]({})", text, target),
+            Format::ReStructuredText => format!(":doc:`{} <{}>`", text, target),
+            Format::AsciiDoc => format!("xref:{}[{}]", target, text),
+        }
+    }
+
+    // The regular expression which finds a link made by `link` above, capturing the text as
+    // `title` and the file as `target`.
+    fn link_pattern(&self) -> &'static str {
+        match self {
+            Format::Markdown => r"\[(?P<title>[^\]]*)\]\((?P<target>[^)]*)\)",
+            Format::ReStructuredText => r":doc:`(?P<title>[^`<]*?)\s*<(?P<target>[^>]*)>`",
+            Format::AsciiDoc => r"xref:(?P<target>[^\[\s]*)\[(?P<title>[^\]]*)\]",
+        }
+    }
+}
+
+// The statuses a record can be given. Each one is written into the Status block using the
+// template string (e.g. "Approved on DATE"), after translation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Proposed,
+    Approved,
+    Rejected,
+}
+
+impl Status {
+    pub const ALL: [Status; 3] = [Status::Proposed, Status::Approved, Status::Rejected];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Status::Proposed => "Proposed",
+            Status::Approved => "Approved",
+            Status::Rejected => "Rejected",
+        }
+    }
+
+    pub fn template(&self) -> &'static str {
+        match self {
+            Status::Proposed => "Proposed on DATE",
+            Status::Approved => "Approved on DATE",
+            Status::Rejected => "Rejected on DATE",
+        }
+    }
+
+    // The translated text before the date, e.g. "Approved on", which starts a status line.
+    pub fn prefix(&self, config: &Config) -> String {
+        config.status_prefix(self.name())
+    }
+}
+
+// The ways that two records can refer to each other. Each one is written into the Status block
+// using the template string (e.g. "Supersedes #"), after translation, where # is the link.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum RelationKind {
+    Supersedes,
+    SupersededBy,
+    Deprecates,
+    DeprecatedBy,
+    Amends,
+    AmendedBy,
+    LinkedTo,
+}
+
+impl RelationKind {
+    pub const ALL: [RelationKind; 7] = [
+        RelationKind::Supersedes,
+        RelationKind::SupersededBy,
+        RelationKind::Deprecates,
+        RelationKind::DeprecatedBy,
+        RelationKind::Amends,
+        RelationKind::AmendedBy,
+        RelationKind::LinkedTo,
+    ];
+
+    pub fn template(&self) -> &'static str {
+        match self {
+            RelationKind::Supersedes => "Supersedes #",
+            RelationKind::SupersededBy => "Superseded by #",
+            RelationKind::Deprecates => "Deprecates #",
+            RelationKind::DeprecatedBy => "Deprecated by #",
+            RelationKind::Amends => "Amends #",
+            RelationKind::AmendedBy => "Amended by #",
+            RelationKind::LinkedTo => "Linked to #",
+        }
+    }
+
+    // The relation the other record should hold, pointing back at this one.
+    pub fn reciprocal(&self) -> RelationKind {
+        match self {
+            RelationKind::Supersedes => RelationKind::SupersededBy,
+            RelationKind::SupersededBy => RelationKind::Supersedes,
+            RelationKind::Deprecates => RelationKind::DeprecatedBy,
+            RelationKind::DeprecatedBy => RelationKind::Deprecates,
+            RelationKind::Amends => RelationKind::AmendedBy,
+            RelationKind::AmendedBy => RelationKind::Amends,
+            RelationKind::LinkedTo => RelationKind::LinkedTo,
+        }
+    }
+}
+
+// A link from one record to another, e.g. "Superseded by [3. Use bar](0003-use-bar.md)".
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Relation {
+    pub kind: RelationKind,
+    // The file being linked to, relative to the record directory.
+    pub target: String,
+    // The text of the link, normally "NUMBER. TITLE".
+    pub title: String,
+    pub reason: Option<String>,
+}
+
+impl Relation {
+    pub fn new(kind: RelationKind, target: &Record, base_path: &Path) -> Relation {
+        Relation {
+            kind,
+            target: target.relative_path(base_path),
+            title: target.link_text(),
+            reason: None,
+        }
+    }
+
+    // A relation to another record in the same directory, given in the front matter by its number
+    // (or slug), titled with its number and slug until `find_siblings` finds the record.
+    fn to_sibling(kind: RelationKind, target: String, numbered: bool) -> Relation {
+        let (number, slug) = number_and_slug_from_path(Path::new(&target), numbered);
+        Relation {
+            kind,
+            title: if number > 0 {
+                format!("{}. {}", number, slug)
+            } else {
+                slug
+            },
+            target,
+            reason: None,
+        }
+    }
+
+    // Write the relation out in the language and format of the record holding it.
+    pub fn render(&self, format: Format, config: &Config) -> String {
+        let mut line = config.translate(self.kind.template());
+        if let Some(reason) = &self.reason {
+            line.push(' ');
+            line.push_str(&config.translate("for the reason %"));
+            line = line.replacen('%', reason, 1);
+        }
+        line.replacen('#', &format.link(&self.title, &self.target), 1)
+    }
+
+    // Try to read a line from the Status block as a relation, in any of the known kinds.
+    pub fn parse(line: &str, format: Format, config: &Config) -> Option<Relation> {
+        for (kind, re_relation) in relation_patterns(format, config).iter() {
+            if let Some(captures) = re_relation.captures(line) {
+                return Some(Relation {
+                    kind: *kind,
+                    target: captures["target"].to_string(),
+                    title: captures["title"].to_string(),
+                    reason: captures
+                        .name("reason")
+                        .map(|reason| reason.as_str().to_string()),
+                });
+            }
+        }
+        None
+    }
+}
+
+// The regular expressions which read each kind of relation in a format.
+pub(crate) type RelationPatterns = Rc<Vec<(RelationKind, Regex)>>;
+
+// The regular expressions which read each kind of relation in this format, in the language of the
+// config. They're made the first time they're needed, and kept in the config after that.
+fn relation_patterns(format: Format, config: &Config) -> RelationPatterns {
+    if let Some(patterns) = config.relation_patterns.borrow().get(&format) {
+        return Rc::clone(patterns);
+    }
+    let reason_template = config.translate("for the reason %");
+    let (reason_prefix, reason_suffix) = split_marker(&reason_template, '%');
+    let str_reason = format!(
+        r"(?:\s+{}(?P<reason>.*?){})?",
+        regex::escape(reason_prefix),
+        regex::escape(reason_suffix)
+    );
+    let patterns: Vec<(RelationKind, Regex)> = RelationKind::ALL
+        .iter()
+        .map(|kind| {
+            let template = config.translate(kind.template());
+            let (prefix, suffix) = split_marker(&template, '#');
+            let re_relation = Regex::new(&format!(
+                r"^\s*{}{}{}{}\s*$",
+                regex::escape(prefix),
+                format.link_pattern(),
+                regex::escape(suffix),
+                str_reason
+            ))
+            .unwrap();
+            (*kind, re_relation)
+        })
+        .collect();
+    let patterns = Rc::new(patterns);
+    config
+        .relation_patterns
+        .borrow_mut()
+        .insert(format, Rc::clone(&patterns));
+    patterns
+}
+
+// A heading in the record and the lines of text below it, up to the next heading.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Section {
+    pub heading: String,
+    // For Markdown and AsciiDoc, the number of # or = characters in the heading.
+    pub level: usize,
+    pub body: Vec<String>,
+}
+
+// A decision record, read from a file and broken into its parts. Changes are made to the fields
+// here, and then written back to the file with `save`.
+#[derive(Clone, Debug)]
+pub struct Record {
+    pub number: i32,
+    pub slug: String,
+    pub path: PathBuf,
+    pub format: Format,
+    pub title: String,
+    pub date: String,
+    // The status lines (e.g. "Approved on 2020-01-01"), in the order they were written. These are
+    // never removed, so they form the history of the record.
+    pub status: Vec<String>,
+    pub relations: Vec<Relation>,
+    pub sections: Vec<Section>,
+    // The YAML front matter, if the record has any. The fields the record keeps (id, title, date,
+    // status and the relations) are brought up to date whenever the record is written.
+    pub front_matter: Option<FrontMatter>,
+    // Anything before the title, which is kept as-is.
+    header: Vec<String>,
+    // The lines between the title and the first heading, including the "Date:" line.
+    preamble: Vec<String>,
+    // The (translated) heading of the Status block.
+    status_heading: String,
+    // Whether the status and relations are kept in the front matter instead of a Status block,
+    // as MADR records do.
+    status_in_front_matter: bool,
+    // Whether the Status block puts the newest status first, as older versions of this tool did.
+    status_newest_first: bool,
+    // The length of the #### lines above and below the title of a Restructured Text record.
+    title_rule_length: usize,
+}
+
+impl Record {
+    pub fn load(path: &Path, config: &Config) -> Result<Record, Error> {
+        let content = read_to_string(path)?;
+        let mut record = Record::parse(path, &content, config);
+        record.find_siblings(
+            config,
+            |directory| {
+                read_dir(directory)?
+                    .map(|entry| entry.map(|entry| entry.path()))
+                    .collect()
+            },
+            |path| read_to_string(path),
+        )?;
+        Ok(record)
+    }
+
+    pub fn parse(path: &Path, content: &str, config: &Config) -> Record {
+        let format = Format::from_path(path)
+            .or_else(|| Format::from_extension(&config.template_format))
+            .unwrap_or(Format::Markdown);
+        let (number, slug) = number_and_slug_from_path(path, config.numbered);
+        let mut record = Record {
+            number,
+            slug,
+            path: path.to_path_buf(),
+            format,
+            title: String::new(),
+            date: String::new(),
+            status: Vec::new(),
+            relations: Vec::new(),
+            sections: Vec::new(),
+            front_matter: None,
+            header: Vec::new(),
+            preamble: Vec::new(),
+            status_heading: config.translate("Status"),
+            status_in_front_matter: false,
+            status_newest_first: false,
+            title_rule_length: 0,
+        };
+
+        let mut lines: Vec<String> = content.lines().map(String::from).collect();
+
+        // Take the front matter off the top of the record, if there is any.
+        if lines.first().map(|line| line.trim_end()) == Some(front_matter::DELIMITER) {
+            if let Some(end) = lines
+                .iter()
+                .skip(1)
+                .position(|line| line.trim_end() == front_matter::DELIMITER)
+            {
+                let rest = lines.split_off(end + 2);
+                record.front_matter = Some(FrontMatter::parse(&lines[1..end + 1]));
+                lines = rest;
+            }
+        }
+
+        let blocks = match format {
+            Format::Markdown => split_markdown(&lines),
+            Format::ReStructuredText => split_restructured_text(&lines),
+            Format::AsciiDoc => split_asciidoc(&lines),
+        };
+
+        let mut found_title = false;
+        for block in blocks {
+            match block {
+                Block::Text(text) => {
+                    if found_title {
+                        match record.sections.last_mut() {
+                            Some(section) => section.body.extend(text),
+                            None => record.preamble.extend(text),
+                        }
+                    } else {
+                        record.header.extend(text);
+                    }
+                }
+                Block::Title(title, rule_length) => {
+                    found_title = true;
+                    record.title_rule_length = rule_length;
+                    // Records without numbers keep any number at the start of the title in it.
+                    let (title_number, split) = split_title(&title);
+                    match title_number {
+                        Some(title_number) if config.numbered => {
+                            if record.number == 0 {
+                                record.number = title_number;
+                            }
+                            record.title = split;
+                        }
+                        _ if config.numbered => record.title = split,
+                        _ => record.title = title.trim().to_string(),
+                    }
+                }
+                Block::Heading(heading, level) => {
+                    found_title = true;
+                    record.sections.push(Section {
+                        heading,
+                        level,
+                        body: Vec::new(),
+                    });
+                }
+            }
+        }
+
+        // A record without a title still has all of its text kept.
+        if !found_title {
+            record.preamble = std::mem::take(&mut record.header);
+        }
+
+        trim_blank_lines(&mut record.header);
+        trim_blank_lines(&mut record.preamble);
+        for section in &mut record.sections {
+            trim_blank_lines(&mut section.body);
+        }
+
+        for line in &record.preamble {
+            if let Some(captures) = RE_DATE_LINE.captures(line) {
+                record.date = captures[2].to_string();
+                break;
+            }
+        }
+
+        if record.date.is_empty() {
+            if let Some(date) = record.front_matter.as_ref().and_then(|fm| fm.text("date")) {
+                record.date = date.to_string();
+            }
+        }
+
+        // Split the Status block into the status lines and the relations.
+        let status_heading = record.status_heading.clone();
+        if let Some(section) = record.section_mut(&status_heading) {
+            let body = std::mem::take(&mut section.body);
+            for line in body {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match Relation::parse(&line, format, config) {
+                    Some(relation) => record.relations.push(relation),
+                    None => record.status.push(line.trim().to_string()),
+                }
+            }
+            record.status_newest_first = is_newest_first(&record.status, config);
+        } else if let Some(front_matter) = &record.front_matter {
+            // Without a Status block, the status and relations come from the front matter.
+            if let Some(status) = front_matter.text("status") {
+                record.status_in_front_matter = true;
+                record.status = front_matter.list("history");
+                if record.status.is_empty() && !status.is_empty() {
+                    record.status.push(status.to_string());
+                }
+                // These name the other records by number (or slug), until `find_siblings`, and
+                // may be followed by the reason, e.g. "3: It needs a cache".
+                for (key, kind) in FRONT_MATTER_RELATIONS {
+                    for entry in front_matter.list(key) {
+                        let (id, reason) = match entry.split_once(": ") {
+                            Some((id, reason)) => (id.to_string(), Some(reason.to_string())),
+                            None => (entry, None),
+                        };
+                        let mut relation = Relation::to_sibling(kind, id, config.numbered);
+                        relation.reason = reason;
+                        record.relations.push(relation);
+                    }
+                }
+            }
+        }
+
+        record
+    }
+
+    // Point the relations from the front matter, which name other records by number (or slug), at
+    // the files of those records, titled as those records are. `list_dir` gives the files in the
+    // record's directory, and `read` the text of one of them; they're only called if there are
+    // relations to find.
+    pub(crate) fn find_siblings(
+        &mut self,
+        config: &Config,
+        list_dir: impl FnOnce(&Path) -> Result<Vec<PathBuf>, Error>,
+        read: impl Fn(&Path) -> Result<String, Error>,
+    ) -> Result<(), Error> {
+        if !self.status_in_front_matter || self.relations.is_empty() {
+            return Ok(());
+        }
+        let directory = self.path.parent().unwrap_or_else(|| Path::new(""));
+        let files = list_dir(directory)?;
+        for relation in &mut self.relations {
+            if let Some(target) = find_sibling(&files, &relation.target, config.numbered) {
+                let path = directory.join(&target);
+                relation.title = Record::parse(&path, &read(&path)?, config).link_text();
+                relation.target = target;
+            }
+        }
+        Ok(())
+    }
+
+    // Write the record back to the file it was read from.
+    pub fn save(&self, config: &Config) -> Result<(), Error> {
+        let mut file_object = File::create(&self.path)?;
+        file_object.write_all(self.render(config).as_bytes())?;
+        Ok(())
+    }
+
+    // Turn the record back into the text of the file.
+    pub fn render(&self, config: &Config) -> String {
+        let mut blocks: Vec<Vec<String>> = Vec::new();
+
+        if self.front_matter.is_some() || config.front_matter {
+            blocks.push(self.updated_front_matter(config).render());
+        }
+        if !self.header.is_empty() {
+            blocks.push(self.header.clone());
+        }
+        blocks.push(self.render_title());
+
+        let preamble: Vec<String> = self
+            .preamble
+            .iter()
+            .map(|line| match RE_DATE_LINE.captures(line) {
+                Some(captures) => format!("{}{}", &captures[1], self.date),
+                None => line.clone(),
+            })
+            .collect();
+        if !preamble.is_empty() {
+            blocks.push(preamble);
+        }
+
+        for section in &self.sections {
+            blocks.push(self.render_heading(section));
+            if section.heading == self.status_heading {
+                for line in self.status_block(config) {
+                    blocks.push(vec![line]);
+                }
+            } else if !section.body.is_empty() {
+                blocks.push(section.body.clone());
+            }
+        }
+
+        let mut content = blocks
+            .iter()
+            .map(|block| block.join("\u{000A}"))
+            .collect::<Vec<String>>()
+            .join("\u{000A}\u{000A}");
+        content.push('\u{000A}');
+        content
+    }
+
+    // The lines of the Status block, being the status lines followed by the relations.
+    pub fn status_block(&self, config: &Config) -> Vec<String> {
+        let mut lines: Vec<String> = self.status.clone();
+        for relation in &self.relations {
+            lines.push(relation.render(self.format, config));
+        }
+        lines
+    }
+
+    // The line which best describes the status the record is currently in: the relation which
+    // superseded or deprecated it, or the latest status line, or failing those, the first relation.
+    pub fn current_status(&self, config: &Config) -> String {
+        self.relations
+            .iter()
+            .find(|relation| {
+                matches!(
+                    relation.kind,
+                    RelationKind::SupersededBy | RelationKind::DeprecatedBy
+                )
+            })
+            .map(|relation| relation.render(self.format, config))
+            .or_else(|| self.status_history().pop())
+            .or_else(|| {
+                self.relations
+                    .first()
+                    .map(|relation| relation.render(self.format, config))
+            })
+            .unwrap_or_default()
+    }
+
+    // The status lines in the order the changes happened, which is the order they were written
+    // in. Records from older versions of this tool put the newest status first, so their Status
+    // block is read from the bottom up (see `is_newest_first`).
+    pub fn status_history(&self) -> Vec<String> {
+        let mut history: Vec<String> = self.status.clone();
+        if self.status_newest_first {
+            history.reverse();
+        }
+        history
+    }
+
+    // The date of the latest status change, if its line has one.
+    pub fn last_status_date(&self) -> Option<String> {
+        self.status_history()
+            .iter()
+            .rev()
+            .find_map(|line| RE_DATE.find(line))
+            .map(|date| date.as_str().to_string())
+    }
+
+    // The lifecycle of the record as plain text: each status change in the order it happened,
+    // followed by the links to other records, e.g. "Superseded by 4. Use bar".
+    pub fn history(&self, config: &Config) -> Vec<String> {
+        let mut lines = self.status_history();
+        for relation in &self.relations {
+            let mut line =
+                config
+                    .translate(relation.kind.template())
+                    .replacen('#', &relation.title, 1);
+            if let Some(reason) = &relation.reason {
+                line.push(' ');
+                line.push_str(
+                    &config
+                        .translate("for the reason %")
+                        .replacen('%', reason, 1),
+                );
+            }
+            lines.push(line);
+        }
+        lines
+    }
+
+    // The name of the status the record is in, e.g. "Approved" or "Superseded". Records which
+    // have been superseded or deprecated take that status, otherwise it's worked out from the
+    // current status line. A status line which isn't recognised is returned as it is.
+    pub fn status_name(&self, config: &Config) -> String {
+        let has_relation = |kind| self.relations.iter().any(|relation| relation.kind == kind);
+        if has_relation(RelationKind::SupersededBy) {
+            return String::from("Superseded");
+        }
+        if has_relation(RelationKind::DeprecatedBy) {
+            return String::from("Deprecated");
+        }
+        match self.status_history().pop() {
+            Some(line) => line_status_name(&line, config),
+            None => String::new(),
+        }
+    }
+
+    // The front matter, with the fields kept by the record brought up to date from the rest of
+    // the record. Any other keys, like the tags, are left as they are.
+    pub fn updated_front_matter(&self, config: &Config) -> FrontMatter {
+        let mut front_matter = self.front_matter.clone().unwrap_or_default();
+        front_matter.set("id", Value::Text(self.identifier()));
+        front_matter.set("title", Value::Text(self.title.clone()));
+        front_matter.set("date", Value::Text(self.date.clone()));
+        let status = self.status_name(config);
+        let status = match status.as_str() {
+            "Approved" if self.status_in_front_matter => String::from("accepted"),
+            _ => status.to_lowercase(),
+        };
+        front_matter.set("status", Value::Text(status));
+        if self.status_in_front_matter {
+            front_matter.set("history", Value::List(self.status.clone()));
+        }
+        for (key, kind) in FRONT_MATTER_RELATIONS {
+            let targets: Vec<String> = self
+                .relations
+                .iter()
+                .filter(|relation| relation.kind == kind)
+                .map(|relation| {
+                    let (number, slug) =
+                        number_and_slug_from_path(Path::new(&relation.target), config.numbered);
+                    let id = if number > 0 { number.to_string() } else { slug };
+                    match &relation.reason {
+                        Some(reason) => format!("{}: {}", id, reason),
+                        None => id,
+                    }
+                })
+                .collect();
+            front_matter.set(key, Value::List(targets));
+        }
+        if front_matter.get("tags").is_none() {
+            front_matter.set("tags", Value::List(Vec::new()));
+        }
+        front_matter
+    }
+
+    // The number of the record, or the slug if it has no number.
+    pub fn identifier(&self) -> String {
+        if self.number > 0 {
+            self.number.to_string()
+        } else {
+            self.slug.clone()
+        }
+    }
+
+    // The heading of the section holding the status lines and relations, e.g. "Status".
+    pub fn status_heading(&self) -> &str {
+        &self.status_heading
+    }
+
+    // The text used when linking to this record, e.g. "1. Record architecture decisions".
+    pub fn link_text(&self) -> String {
+        if self.title.is_empty() {
+            return self.path_filename();
+        }
+        if self.number > 0 {
+            format!("{}. {}", self.number, self.title)
+        } else {
+            self.title.clone()
+        }
+    }
+
+    // The path to this record from the base path (normally the record directory).
+    pub fn relative_path(&self, base_path: &Path) -> String {
+        match diff_paths(&self.path, base_path) {
+            Some(path) => path.display().to_string(),
+            None => self.path_filename(),
+        }
+    }
+
+    // Add a status line after the others in the Status block, making it the current status.
+    pub fn add_status(&mut self, status: String) {
+        if !self.status_in_front_matter {
+            self.ensure_status_section();
+        }
+        // Put the lines of an older, newest first, record in order before adding to them.
+        self.status = self.status_history();
+        self.status_newest_first = false;
+        self.status.push(status);
+    }
+
+    // Add a relation at the end of the Status block.
+    pub fn add_relation(&mut self, relation: Relation) {
+        if !self.status_in_front_matter {
+            self.ensure_status_section();
+        }
+        self.relations.push(relation);
+    }
+
+    pub fn section(&self, heading: &str) -> Option<&Section> {
+        self.sections
+            .iter()
+            .find(|section| section.heading == heading)
+    }
+
+    pub fn section_mut(&mut self, heading: &str) -> Option<&mut Section> {
+        self.sections
+            .iter_mut()
+            .find(|section| section.heading == heading)
+    }
+
+    // A copy of the record in another format, named with that format's extension. The text above
+    // the title, before the first heading and in each section is passed through `convert_text`.
+    pub(crate) fn with_format(
+        &self,
+        format: Format,
+        convert_text: impl Fn(&[String]) -> Vec<String>,
+    ) -> Record {
+        let mut record = self.clone();
+        record.format = format;
+        record.path = self.path.with_extension(format.extension());
+        record.header = convert_text(&self.header);
+        record.preamble = convert_text(&self.preamble);
+        record.title_rule_length = 0;
+        for section in &mut record.sections {
+            section.body = convert_text(&section.body);
+        }
+        record
+    }
+
+    fn ensure_status_section(&mut self) {
+        if self.section(&self.status_heading).is_none() {
+            let level = self
+                .sections
+                .first()
+                .map(|section| section.level)
+                .unwrap_or(2);
+            self.sections.insert(
+                0,
+                Section {
+                    heading: self.status_heading.clone(),
+                    level,
+                    body: Vec::new(),
+                },
+            );
+        }
+    }
+
+    fn path_filename(&self) -> String {
+        self.path
+            .file_name()
+            .map(|filename| filename.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+
+    fn render_title(&self) -> Vec<String> {
+        let title = if self.number > 0 {
+            format!("{}. {}", self.number, self.title)
+        } else {
+            self.title.clone()
+        };
+        match self.format {
+            Format::Markdown => vec![format!("# {}", title)],
+            Format::ReStructuredText => {
+                let rule = "#".repeat(self.title_rule_length.max(title.chars().count()));
+                vec![rule.clone(), title, rule]
+            }
+            Format::AsciiDoc => vec![format!("= {}", title)],
+        }
+    }
+
+    fn render_heading(&self, section: &Section) -> Vec<String> {
+        match self.format {
+            Format::Markdown => vec![format!("{} {}", "#".repeat(section.level), section.heading)],
+            Format::ReStructuredText => {
+                let rule = "*".repeat(section.heading.chars().count());
+                vec![rule.clone(), section.heading.clone(), rule]
+            }
+            Format::AsciiDoc => vec![format!("{} {}", "=".repeat(section.level), section.heading)],
+        }
+    }
+}
+
+// The front matter keys which list the records linked to by each kind of relation. These are also
+// the names given to the relations in JSON output.
+pub(crate) const FRONT_MATTER_RELATIONS: [(&str, RelationKind); 7] = [
+    ("supersedes", RelationKind::Supersedes),
+    ("superseded_by", RelationKind::SupersededBy),
+    ("deprecates", RelationKind::Deprecates),
+    ("deprecated_by", RelationKind::DeprecatedBy),
+    ("amends", RelationKind::Amends),
+    ("amended_by", RelationKind::AmendedBy),
+    ("links", RelationKind::LinkedTo),
+];
+
+// The building blocks of a record, as found by the format specific splitters below.
+enum Block {
+    Text(Vec<String>),
+    // The title text, and (for Restructured Text) the length of the line above and below it.
+    Title(String, usize),
+    // The heading text, and its level.
+    Heading(String, usize),
+}
+
+// Split Markdown into the title (the first `# ` heading), the headings below it and the text
+// between them. Only a line starting with # is a heading, so indented code blocks are left alone,
+// as are the lines inside fenced code blocks. A closing sequence of #s is only taken off the
+// heading if there's a space before it, so "# Adopt F#" keeps its #.
+fn split_markdown(lines: &[String]) -> Vec<Block> {
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut text: Vec<String> = Vec::new();
+    // The fence which opened the code block the line is in, if it's in one.
+    let mut fence: Option<String> = None;
+    let mut found_title = false;
+
+    for line in lines {
+        if let Some(captures) = RE_MARKDOWN_FENCE.captures(line) {
+            let this_fence = &captures[1];
+            match &fence {
+                Some(open) if this_fence.starts_with(open.as_str()) => fence = None,
+                None => fence = Some(this_fence.to_string()),
+                Some(_) => {}
+            }
+            text.push(line.clone());
+            continue;
+        }
+        if fence.is_none() {
+            if let Some(captures) = RE_MARKDOWN_HEADING.captures(line) {
+                blocks.push(Block::Text(std::mem::take(&mut text)));
+                let level = captures[1].len();
+                if level == 1 && !found_title {
+                    found_title = true;
+                    blocks.push(Block::Title(captures[2].to_string(), 0));
+                } else {
+                    blocks.push(Block::Heading(captures[2].to_string(), level));
+                }
+                continue;
+            }
+        }
+        text.push(line.clone());
+    }
+    blocks.push(Block::Text(text));
+    blocks
+}
+
+// Split Restructured Text into the title (text with a line of # above and below it), the headings
+// (text with a line of * above and below it) and the text between them.
+fn split_restructured_text(lines: &[String]) -> Vec<Block> {
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut text: Vec<String> = Vec::new();
+    let mut index = 0;
+
+    while index < lines.len() {
+        if index + 2 < lines.len() && !lines[index + 1].trim().is_empty() {
+            let (above, heading, below) = (&lines[index], &lines[index + 1], &lines[index + 2]);
+            if RE_TITLE_RULE.is_match(above) && RE_TITLE_RULE.is_match(below) {
+                blocks.push(Block::Text(std::mem::take(&mut text)));
+                blocks.push(Block::Title(
+                    heading.trim().to_string(),
+                    above.trim().chars().count(),
+                ));
+                index += 3;
+                continue;
+            }
+            if RE_HEADING_RULE.is_match(above) && RE_HEADING_RULE.is_match(below) {
+                blocks.push(Block::Text(std::mem::take(&mut text)));
+                blocks.push(Block::Heading(heading.trim().to_string(), 2));
+                index += 3;
+                continue;
+            }
+        }
+        text.push(lines[index].clone());
+        index += 1;
+    }
+    blocks.push(Block::Text(text));
+    blocks
+}
+
+// Split AsciiDoc into the title (the `= ` line), the sections (`== `, `=== ` and so on) and the
+// text between them. Lines inside listing, literal and fenced blocks are ignored.
+fn split_asciidoc(lines: &[String]) -> Vec<Block> {
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut text: Vec<String> = Vec::new();
+    let mut delimiter: Option<String> = None;
+    let mut found_title = false;
+
+    for line in lines {
+        if RE_ASCIIDOC_DELIMITER.is_match(line) {
+            let this_delimiter = line.trim_end().to_string();
+            match &delimiter {
+                Some(open) if *open == this_delimiter => delimiter = None,
+                None => delimiter = Some(this_delimiter),
+                Some(_) => {}
+            }
+        }
+        if delimiter.is_none() {
+            if let Some(captures) = RE_ASCIIDOC_HEADING.captures(line) {
+                blocks.push(Block::Text(std::mem::take(&mut text)));
+                let level = captures[1].len();
+                if level == 1 && !found_title {
+                    found_title = true;
+                    blocks.push(Block::Title(captures[2].to_string(), 0));
+                } else {
+                    blocks.push(Block::Heading(captures[2].to_string(), level));
+                }
+                continue;
+            }
+        }
+        text.push(line.clone());
+    }
+    blocks.push(Block::Text(text));
+    blocks
+}
+
+// Split "12. Some title" into the number and the title.
+fn split_title(title: &str) -> (Option<i32>, String) {
+    match RE_NUMBERED_TITLE.captures(title) {
+        Some(captures) => (captures[1].parse().ok(), captures[2].to_string()),
+        None => (None, title.trim().to_string()),
+    }
+}
+
+// Find the number and slug of a record from a filename like `0012-some-title.md`. When records
+// aren't `numbered`, a file like `2024-roadmap.md` is just a slug.
+pub(crate) fn number_and_slug_from_path(path: &Path, numbered: bool) -> (i32, String) {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    match RE_NUMBERED_FILENAME.captures(&stem) {
+        Some(captures) if numbered => (captures[1].parse().unwrap_or(0), captures[2].to_string()),
+        _ => (0, stem),
+    }
+}
+
+// Read the status words MADR uses (e.g. "accepted" or "superseded by ADR-0005") as the names of
+// our statuses.
+pub(crate) fn madr_status_name(status: &str) -> Option<String> {
+    let word = status.split_whitespace().next()?.to_lowercase();
+    let name = match word.as_str() {
+        "proposed" => "Proposed",
+        "accepted" | "approved" => "Approved",
+        "rejected" => "Rejected",
+        "superseded" => "Superseded",
+        "deprecated" => "Deprecated",
+        _ => return None,
+    };
+    Some(name.to_string())
+}
+
+// The status a line from the Status block records, e.g. "Approved" for "Approved on 2024-01-31".
+fn line_status_name(line: &str, config: &Config) -> String {
+    // The longest matching prefix wins, so "Proposed again on" isn't read as "Proposed".
+    let mut names: Vec<&str> = config.statuses.iter().map(String::as_str).collect();
+    names.extend(Status::ALL.iter().map(|status| status.name()));
+    names
+        .iter()
+        .map(|name| (config.status_prefix(name), *name))
+        .filter(|(prefix, _)| !prefix.is_empty() && line.starts_with(prefix.as_str()))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, name)| name.to_string())
+        .or_else(|| config.find_status(line).map(String::from))
+        .or_else(|| madr_status_name(line))
+        .unwrap_or_else(|| line.to_string())
+}
+
+// How far along the workflow a status line is: the statuses in the order they're configured,
+// then superseded and deprecated. A line with an unknown status has no place.
+fn status_rank(line: &str, config: &Config) -> Option<usize> {
+    let name = line_status_name(line, config);
+    let mut names: Vec<&str> = config.statuses.iter().map(String::as_str).collect();
+    for status in Status::ALL {
+        if !names.contains(&status.name()) {
+            names.push(status.name());
+        }
+    }
+    names.extend(["Superseded", "Deprecated"]);
+    names.iter().position(|known| *known == name)
+}
+
+// Whether the lines of a Status block were written newest first, as older versions of this tool
+// did. Reading down a block written in order, each change is on a later date than the one above
+// it, or on the same date and further along the workflow (e.g. Approved below Proposed). A block
+// which only ever goes the other way is newest first; one which goes both ways is taken as written.
+fn is_newest_first(lines: &[String], config: &Config) -> bool {
+    let steps: Vec<(Option<&str>, Option<usize>)> = lines
+        .iter()
+        .map(|line| {
+            (
+                RE_DATE.find(line).map(|date| date.as_str()),
+                status_rank(line, config),
+            )
+        })
+        .collect();
+    let mut forwards = false;
+    let mut backwards = false;
+    for pair in steps.windows(2) {
+        let (earlier, later) = (pair[0], pair[1]);
+        let order = match (earlier.0, later.0) {
+            (Some(earlier), Some(later)) if earlier != later => earlier.cmp(later),
+            _ => match (earlier.1, later.1) {
+                (Some(earlier), Some(later)) => earlier.cmp(&later),
+                _ => Ordering::Equal,
+            },
+        };
+        match order {
+            Ordering::Less => forwards = true,
+            Ordering::Greater => backwards = true,
+            Ordering::Equal => {}
+        }
+    }
+    backwards && !forwards
+}
+
+// Find the file name of the record, out of the files in its directory, with the number (or slug)
+// given in the front matter of another record.
+fn find_sibling(files: &[PathBuf], id: &str, numbered: bool) -> Option<String> {
+    let number: i32 = id.trim_start_matches("ADR-").parse().unwrap_or(0);
+    files
+        .iter()
+        .find(|path| {
+            let (sibling_number, sibling_slug) = number_and_slug_from_path(path, numbered);
+            if number > 0 {
+                sibling_number == number
+            } else {
+                sibling_slug == id
+            }
+        })
+        .and_then(|path| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+        })
+}
+
+// Split a template like "Superseded by #" around the marker, into "Superseded by " and "".
+fn split_marker(template: &str, marker: char) -> (&str, &str) {
+    match template.find(marker) {
+        Some(position) => (&template[..position], &template[position + 1..]),
+        None => (template, ""),
+    }
+}
+
+fn trim_blank_lines(lines: &mut Vec<String>) {
+    while lines.first().is_some_and(|line| line.trim().is_empty()) {
+        lines.remove(0);
+    }
+    while lines.last().is_some_and(|line| line.trim().is_empty()) {
+        lines.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::create_dir_all;
+
+    // A config with the defaults, found from a directory which only has the record directory.
+    fn default_config(name: &str) -> Config {
+        let root =
+            std::env::temp_dir().join(format!("decision-record-{}-{}", name, std::process::id()));
+        create_dir_all(root.join("doc/decision_records")).unwrap();
+        let config = crate::config::load_config_from(&root).unwrap();
+        let _ = std::fs::remove_dir_all(&root);
+        config
+    }
+
+    fn record_with_status(config: &Config, status: &[&str]) -> Record {
+        let content = format!(
+            "# 1. Use foo\n\nDate: 2024-01-10\n\n## Status\n\n{}\n\n## Context\n\nText.\n",
+            status.join("\n\n")
+        );
+        Record::parse(Path::new("0001-use-foo.md"), &content, config)
+    }
+
+    #[test]
+    fn reads_a_same_day_newest_first_block_from_the_bottom() {
+        let config = default_config("same-day-legacy");
+        let mut record = record_with_status(
+            &config,
+            &["Approved on 2024-01-10", "Proposed on 2024-01-10"],
+        );
+        assert_eq!(record.status_name(&config), "Approved");
+        assert_eq!(
+            record.status_history(),
+            vec!["Proposed on 2024-01-10", "Approved on 2024-01-10"]
+        );
+
+        record.add_status(String::from("Rejected on 2024-01-11"));
+        let record = Record::parse(&record.path, &record.render(&config), &config);
+        assert_eq!(record.status_name(&config), "Rejected");
+        assert_eq!(
+            record.status_history(),
+            vec![
+                "Proposed on 2024-01-10",
+                "Approved on 2024-01-10",
+                "Rejected on 2024-01-11"
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_a_same_day_block_in_order_from_the_top() {
+        let config = default_config("same-day-in-order");
+        let record = record_with_status(
+            &config,
+            &["Proposed on 2024-01-10", "Approved on 2024-01-10"],
+        );
+        assert_eq!(record.status_name(&config), "Approved");
+    }
+
+    #[test]
+    fn keeps_the_reason_and_title_of_a_front_matter_link() {
+        let config = default_config("front-matter-link");
+        let content = "---\nstatus: accepted\nlinks: [\"2: It needs a cache, \\\"soon\\\"\"]\n---\n\n# Use foo\n";
+        let mut record = Record::parse(Path::new("0001-use-foo.md"), content, &config);
+        record
+            .find_siblings(
+                &config,
+                |_| Ok(vec![PathBuf::from("0002-add-a-cache.md")]),
+                |_| Ok(String::from("# 2. Add a cache\n\nDate: 2024-01-10\n")),
+            )
+            .unwrap();
+        assert_eq!(record.relations.len(), 1);
+        assert_eq!(record.relations[0].target, "0002-add-a-cache.md");
+        assert_eq!(record.relations[0].title, "2. Add a cache");
+        assert_eq!(
+            record.relations[0].reason.as_deref(),
+            Some("It needs a cache, \"soon\"")
+        );
+
+        let rendered = record.render(&config);
+        assert!(rendered.contains("links: [\"2: It needs a cache, \\\"soon\\\"\"]"));
+        let reread = Record::parse(&record.path, &rendered, &config);
+        assert_eq!(reread.relations[0].reason, record.relations[0].reason);
+    }
+}
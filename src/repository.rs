@@ -0,0 +1,923 @@
+use slug::slugify;
+
+use crate::config::{self, Config};
+use crate::convert;
+use crate::error::Error;
+use crate::record::{number_and_slug_from_path, Format, Record, Relation, RelationKind, Status};
+use crate::staging::{FileChange, Staging};
+use chrono::{DateTime, Local, NaiveDate};
+use lazy_static::lazy_static;
+use pathdiff::diff_paths;
+use regex::Regex;
+use std::collections::HashMap;
+use std::env;
+use std::fs::metadata;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::UNIX_EPOCH;
+
+lazy_static! {
+    static ref RE_RECORD_NUMBER: Regex = Regex::new(r"(?i)^(?:adr|dr)?-?0*(\d+)$").unwrap();
+    static ref RE_NUMBERED_FILE: Regex = Regex::new(r"^(\d{4,})-[^\\/]*\.(md|rst|adoc)$").unwrap();
+    static ref RE_SLUG_FILE: Regex =
+        Regex::new(r"^[a-z0-9]+(-[a-z0-9]+)*\.(md|rst|adoc)$").unwrap();
+}
+
+// A directory of decision records, and the configuration which describes how they're written.
+// Every action which reads or changes the records goes through here. The files an action changes
+// are only written once the whole action has worked, and not at all in a dry run.
+pub struct Repository {
+    config: Config,
+    // The date to write into records, instead of today (see `today`).
+    date: Option<NaiveDate>,
+    // Whether a status change may be dated before the record's latest one.
+    earlier_dates: bool,
+    dry_run: bool,
+    staging: Staging,
+}
+
+// The choices made when creating a new record. Each of the lists holds the records (as typed by
+// the user) which the new record will be linked to.
+#[derive(Clone, Debug, Default)]
+pub struct NewRecord {
+    pub title: String,
+    // If this is not set, the `defaultProposed` value from the config is used.
+    pub status: Option<Status>,
+    pub supersedes: Vec<String>,
+    pub deprecates: Vec<String>,
+    pub amends: Vec<String>,
+    pub links: Vec<String>,
+}
+
+impl Repository {
+    // Find the configuration for the records, starting at this path and working up through the
+    // parent directories (see `config::load_config_from` for what is looked for).
+    pub fn open(path: &Path) -> Result<Repository, Error> {
+        Ok(Repository::from_config(config::load_config_from(path)?))
+    }
+
+    pub fn from_config(config: Config) -> Repository {
+        Repository {
+            config,
+            date: None,
+            earlier_dates: false,
+            dry_run: false,
+            staging: Staging::default(),
+        }
+    }
+
+    // Keep every change in memory rather than writing it, so it can be shown with `changes`.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Repository {
+        self.dry_run = dry_run;
+        self
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    // The files a dry run would have changed.
+    pub fn changes(&self) -> Result<Vec<FileChange>, Error> {
+        Ok(self.staging.changes()?)
+    }
+
+    // Write a file which isn't a record (e.g. an index), in the same way as the records are
+    // written, so that it's left alone in a dry run.
+    pub fn write_file(&self, path: &Path, content: String) -> Result<(), Error> {
+        self.write_files(vec![(path.to_path_buf(), content)])
+    }
+
+    // Write several files, all or none of them.
+    pub fn write_files(&self, files: Vec<(PathBuf, String)>) -> Result<(), Error> {
+        self.change_files(files, &[])
+    }
+
+    // Write several files and remove others which aren't records (e.g. an old config file), all
+    // or none of them.
+    pub fn change_files(
+        &self,
+        files: Vec<(PathBuf, String)>,
+        removed: &[PathBuf],
+    ) -> Result<(), Error> {
+        self.apply(|| {
+            for (path, content) in files {
+                self.staging.write(&path, content);
+            }
+            for path in removed {
+                self.staging.remove(path);
+            }
+            Ok(())
+        })
+    }
+
+    // Use this date for any new record or status change, rather than today.
+    pub fn with_date(mut self, date: NaiveDate) -> Repository {
+        self.date = Some(date);
+        self
+    }
+
+    // Allow status changes dated before the record's latest one, e.g. when filling in its history.
+    pub fn with_earlier_dates(mut self, earlier_dates: bool) -> Repository {
+        self.earlier_dates = earlier_dates;
+        self
+    }
+
+    // The date written into records, as YYYY-MM-DD.
+    pub fn date(&self) -> Result<String, Error> {
+        let date = match self.date {
+            Some(date) => date,
+            None => today()?,
+        };
+        Ok(date.format("%Y-%m-%d").to_string())
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    // Every record in the record directory, in numerical order.
+    pub fn records(&self) -> Result<Vec<Record>, Error> {
+        list_records(&self.record_files()?, self.config.numbered)
+            .iter()
+            .map(|(_, path)| self.load(path))
+            .collect()
+    }
+
+    // Find a single record from the way a person would refer to it: its number (`7`, `0007` or
+    // `ADR-7`), its file name or path, its slug, or a piece of its title which only that record
+    // has. It's an error if nothing matches, or if more than one record does.
+    pub fn find(&self, record: &str) -> Result<Record, Error> {
+        let files = list_records(&self.record_files()?, self.config.numbered);
+        self.find_in(&files, record)
+    }
+
+    // Find each of several records (see `find`), listing the record directory only once.
+    pub fn find_each(&self, records: &[&str]) -> Result<Vec<Record>, Error> {
+        let files = list_records(&self.record_files()?, self.config.numbered);
+        records
+            .iter()
+            .map(|record| self.find_in(&files, record))
+            .collect()
+    }
+
+    // Find a record among these files. The number, file name and slug all come from the name of
+    // the file, so only the records which match are read, unless it comes down to the title.
+    fn find_in(&self, files: &[(i32, PathBuf)], record: &str) -> Result<Record, Error> {
+        let needle = record.trim();
+        if needle.is_empty() {
+            return Err(Error::InvalidInput(String::from("No record was given")));
+        }
+        let load_matching = |matching: &dyn Fn(&(i32, PathBuf)) -> bool| {
+            files
+                .iter()
+                .filter(|file| matching(file))
+                .map(|(_, path)| self.load(path))
+                .collect::<Result<Vec<Record>, Error>>()
+        };
+
+        // A number, optionally zero padded or with an "ADR-" style prefix. Without numbers in the
+        // file names, a slug like `42` or `adr-7` is more likely, so that is looked for first.
+        let by_number = match RE_RECORD_NUMBER.captures(needle) {
+            Some(captures) => {
+                let number: i32 = captures[1].parse().unwrap_or(0);
+                load_matching(&|(file_number, _)| *file_number > 0 && *file_number == number)?
+            }
+            None => Vec::new(),
+        };
+        if self.config.numbered && !by_number.is_empty() {
+            return only_match(needle, by_number);
+        }
+
+        // A file name or path, which only needs the last part to match.
+        let filename = Path::new(needle)
+            .file_name()
+            .map(|filename| filename.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let by_filename = load_matching(&|(_, path)| {
+            path.file_name()
+                .is_some_and(|name| name.to_string_lossy() == filename)
+        })?;
+        if !by_filename.is_empty() {
+            return only_match(needle, by_filename);
+        }
+
+        // The slug, as written in the file name.
+        let slug = slugify(needle);
+        let by_slug = load_matching(&|(_, path)| {
+            let (_, file_slug) = number_and_slug_from_path(path, self.config.numbered);
+            file_slug == needle || file_slug == slug
+        })?;
+        if !by_slug.is_empty() {
+            return only_match(needle, by_slug);
+        }
+        if !by_number.is_empty() {
+            return only_match(needle, by_number);
+        }
+
+        // Anything else is a piece of the title.
+        let fragment = needle.to_lowercase();
+        let by_title: Vec<Record> = load_matching(&|_| true)?
+            .into_iter()
+            .filter(|candidate| candidate.title.to_lowercase().contains(&fragment))
+            .collect();
+        only_match(needle, by_title)
+    }
+
+    // Create a new record from the template, and then link it to any records it supersedes,
+    // deprecates, amends or links to. The new record is returned as it was finally written.
+    pub fn new_record(&self, new_record: &NewRecord) -> Result<Record, Error> {
+        self.apply(|| self.create_record(new_record))
+    }
+
+    fn create_record(&self, new_record: &NewRecord) -> Result<Record, Error> {
+        let date_now = self.date()?;
+
+        // Check every record to be linked to can be found (and, if it's being superseded or
+        // deprecated, that the workflow allows it) before anything is written.
+        let files = list_records(&self.record_files()?, self.config.numbered);
+        for record in &new_record.supersedes {
+            self.check_transition(&self.find_in(&files, record)?, "Superseded")?;
+        }
+        for record in &new_record.deprecates {
+            self.check_transition(&self.find_in(&files, record)?, "Deprecated")?;
+        }
+        for record in new_record.amends.iter().chain(&new_record.links) {
+            self.find_in(&files, record)?;
+        }
+
+        // Look through the paths and find any files which match the naming convention ([0-9][0-9][0-9][0-9]*)
+        // then find the highest number at the start of those, and add one to that number.
+        let max_file_prefix: i32 = list_records(&self.record_files()?, true)
+            .iter()
+            .map(|(number, _)| *number)
+            .max()
+            .unwrap_or(0)
+            + 1;
+
+        // Format the file prefix as 4 digits long, zero padded. Add the title, as a slug (unicode characters, replacing symbols with hyphens)
+        // and the format, like this `0001-some-title.md`
+        // Without numbers, the file is just the slug, e.g. `some-title.md`, which must not
+        // already be taken.
+        let slug = slugify(&new_record.title);
+        let filename: String = if self.config.numbered {
+            format!(
+                "{:0>4}-{}.{}",
+                max_file_prefix, slug, &self.config.template_format
+            )
+        } else {
+            format!("{}.{}", slug, &self.config.template_format)
+        };
+        let absolute_filename: PathBuf = self.config.record_path.join(filename);
+        if !self.config.numbered && self.staging.exists(&absolute_filename) {
+            return Err(Error::RecordExists(slug));
+        }
+
+        // Set the status string, if the status is forced (otherwise use the default, pulled from the config)
+        let status = match new_record.status {
+            Some(status) => self.config.status_template(status.name()),
+            None => self.config.status_template(&self.config.default_status),
+        };
+
+        // Replace the marker values in the template (NUMBER, TITLE, DATE, STATUS) with their values
+        // from above. Records without a number drop the "NUMBER. " from the title.
+        let template = if self.config.numbered {
+            self.config
+                .template_string
+                .replace("NUMBER", &max_file_prefix.to_string())
+        } else {
+            self.config
+                .template_string
+                .replace("NUMBER. ", "")
+                .replace("NUMBER", "")
+        };
+        let new_file_content = template
+            .replace("TITLE", &new_record.title)
+            .replace("STATUS", &status)
+            .replace("DATE", &date_now);
+
+        // Write the file, adding the front matter if the config asks for it.
+        if self.config.front_matter || self.config.madr {
+            self.save(&Record::parse(
+                &absolute_filename,
+                &new_file_content,
+                &self.config,
+            ));
+        } else {
+            self.staging.write(&absolute_filename, new_file_content);
+        }
+
+        // Run all linking activities
+        let number = if self.config.numbered {
+            max_file_prefix.to_string()
+        } else {
+            slug
+        };
+        let links: [(&Vec<String>, RelationKind, Option<String>); 4] = [
+            (&new_record.supersedes, RelationKind::SupersededBy, None),
+            (&new_record.deprecates, RelationKind::DeprecatedBy, None),
+            (&new_record.amends, RelationKind::AmendedBy, None),
+            (
+                &new_record.links,
+                RelationKind::LinkedTo,
+                Some("Linked".to_string()),
+            ),
+        ];
+        for (records, kind, reason) in links {
+            if !records.is_empty() {
+                let from: Vec<&str> = records.iter().map(String::as_str).collect();
+                self.relate(&from, &number, kind, reason)?;
+            }
+        }
+
+        self.load(&absolute_filename)
+    }
+
+    // Add a new status line (e.g. "Approved on 2020-01-01") to the end of the Status block of the
+    // record, making it the current status.
+    pub fn set_status(&self, record: &str, status: Status) -> Result<Record, Error> {
+        self.change_status(record, status.name())
+    }
+
+    // Move the record to one of the statuses in the workflow, by name. Names which aren't in the
+    // workflow, and moves the workflow doesn't allow, are refused.
+    pub fn change_status(&self, record: &str, status: &str) -> Result<Record, Error> {
+        let mut changed = self.change_statuses(&[record], status)?;
+        Ok(changed.remove(0))
+    }
+
+    // Move several records to the same status, as `set_status` does: all of them, or (if any
+    // can't be found, or can't be moved) none of them.
+    pub fn set_statuses(&self, records: &[&str], status: Status) -> Result<Vec<Record>, Error> {
+        self.change_statuses(records, status.name())
+    }
+
+    // Move several records to the same status, by name, as `change_status` does: all of them, or
+    // none of them.
+    pub fn change_statuses(&self, records: &[&str], status: &str) -> Result<Vec<Record>, Error> {
+        self.apply(|| self.move_to_status(records, status))
+    }
+
+    fn move_to_status(&self, records: &[&str], status: &str) -> Result<Vec<Record>, Error> {
+        let status = match self.config.find_status(status) {
+            Some(status) => status.to_string(),
+            None => {
+                return Err(Error::UnknownStatus {
+                    status: status.to_string(),
+                    known: self.config.statuses.clone(),
+                })
+            }
+        };
+
+        // Find and check every record first, taking each one only once.
+        let mut found: Vec<Record> = Vec::new();
+        for record in self.find_each(records)? {
+            if !found.iter().any(|other| other.path == record.path) {
+                self.check_transition(&record, &status)?;
+                found.push(record);
+            }
+        }
+
+        let mut changed: Vec<Record> = Vec::new();
+        for mut this_record in found {
+            let line = self.status_line(&this_record, &status)?;
+            this_record.add_status(line);
+            self.save(&this_record);
+            changed.push(this_record);
+        }
+        Ok(changed)
+    }
+
+    // Link each of the `from` records to the `to` record, optionally giving a reason.
+    pub fn link(
+        &self,
+        from: &[&str],
+        to: &str,
+        reason: Option<&str>,
+    ) -> Result<Vec<Record>, Error> {
+        self.apply(|| {
+            self.relate(
+                from,
+                to,
+                RelationKind::LinkedTo,
+                reason.map(|reason| reason.to_string()),
+            )
+        })
+    }
+
+    // Mark each of the `from` records as superseded by the `to` record.
+    pub fn supersede(&self, from: &[&str], to: &str) -> Result<Vec<Record>, Error> {
+        self.apply(|| self.relate(from, to, RelationKind::SupersededBy, None))
+    }
+
+    // Mark each of the `from` records as amended by the `to` record.
+    pub fn amend(&self, from: &[&str], to: &str) -> Result<Vec<Record>, Error> {
+        self.apply(|| self.relate(from, to, RelationKind::AmendedBy, None))
+    }
+
+    // Mark each of the `from` records as deprecated by the `to` record.
+    pub fn deprecate(&self, from: &[&str], to: &str) -> Result<Vec<Record>, Error> {
+        self.apply(|| self.relate(from, to, RelationKind::DeprecatedBy, None))
+    }
+
+    // Give a record a new title, renaming its file to match (in the same way as `new_record`
+    // names files) and rewriting the links to it in every other record. The renamed record is
+    // returned, followed by the records whose links were changed.
+    pub fn retitle(&self, record: &str, title: &str) -> Result<Vec<Record>, Error> {
+        self.apply(|| self.rename(record, title))
+    }
+
+    fn rename(&self, record: &str, title: &str) -> Result<Vec<Record>, Error> {
+        let mut this_record = self.find(record)?;
+        let old_path = this_record.path.clone();
+        let old_target = this_record.relative_path(&self.config.record_path);
+
+        let slug = slugify(title);
+        let filename = if this_record.number > 0 {
+            format!(
+                "{:0>4}-{}.{}",
+                this_record.number,
+                slug,
+                this_record.format.extension()
+            )
+        } else {
+            format!("{}.{}", slug, this_record.format.extension())
+        };
+        let new_path = self.config.record_path.join(filename);
+        if new_path != old_path && self.staging.exists(&new_path) {
+            return Err(Error::RecordExists(slug));
+        }
+
+        this_record.title = title.trim().to_string();
+        this_record.slug = slug;
+        this_record.path = new_path;
+        if this_record.path != old_path {
+            self.staging.remove(&old_path);
+        }
+        self.save(&this_record);
+
+        let mut changed = vec![this_record.clone()];
+        changed.extend(self.rewrite_links(&old_target, &this_record)?);
+        Ok(changed)
+    }
+
+    // Find records which share a number (which happens when two branches each add a record and
+    // are then merged), and give all but the first of each a new number after the highest one in
+    // use. Which record came first is decided by when it was added to git, or failing that, when
+    // the file was last changed. The renumbered records are renamed, their headings updated and
+    // the links to them in every other record rewritten. Each renumbered record is returned with
+    // the path it used to have.
+    pub fn renumber(&self) -> Result<Vec<(String, Record)>, Error> {
+        self.apply(|| self.give_new_numbers())
+    }
+
+    fn give_new_numbers(&self) -> Result<Vec<(String, Record)>, Error> {
+        let records = self.records()?;
+        let mut next_number = records
+            .iter()
+            .map(|record| record.number)
+            .max()
+            .unwrap_or(0)
+            + 1;
+
+        let mut by_number: Vec<(i32, Vec<Record>)> = Vec::new();
+        for record in records.into_iter().filter(|record| record.number > 0) {
+            match by_number
+                .iter_mut()
+                .find(|(number, _)| *number == record.number)
+            {
+                Some((_, group)) => group.push(record),
+                None => by_number.push((record.number, vec![record])),
+            }
+        }
+
+        let mut renumbered: Vec<(String, Record)> = Vec::new();
+        for (_, mut group) in by_number {
+            if group.len() < 2 {
+                continue;
+            }
+            group.sort_by_key(|record| (added_time(&record.path), record.path.clone()));
+            for mut record in group.into_iter().skip(1) {
+                let old_path = record.path.clone();
+                let old_target = record.relative_path(&self.config.record_path);
+
+                record.number = next_number;
+                next_number += 1;
+                record.path = self.config.record_path.join(format!(
+                    "{:0>4}-{}.{}",
+                    record.number,
+                    record.slug,
+                    record.format.extension()
+                ));
+                self.staging.remove(&old_path);
+                self.save(&record);
+                self.rewrite_links(&old_target, &record)?;
+                renumbered.push((old_target, record));
+            }
+        }
+        Ok(renumbered)
+    }
+
+    // Rewrite records in another format (Markdown or Restructured Text), renaming each file to
+    // the new extension and rewriting the links to it in every other record. With no records
+    // given, every record is converted, and the `fileType` in the config is changed to match,
+    // with the template converted alongside the old one. Each converted record is returned with
+    // the path it used to have.
+    pub fn convert(&self, records: &[&str], to: Format) -> Result<Vec<(String, Record)>, Error> {
+        self.apply(|| self.change_format(records, to))
+    }
+
+    fn change_format(&self, records: &[&str], to: Format) -> Result<Vec<(String, Record)>, Error> {
+        if to == Format::AsciiDoc {
+            return Err(Error::InvalidInput(String::from(
+                "Records can only be converted to Markdown or Restructured Text",
+            )));
+        }
+        if self.config.madr {
+            return Err(Error::InvalidInput(String::from(
+                "MADR records are always Markdown, so they can't be converted",
+            )));
+        }
+        let chosen: Vec<Record> = if records.is_empty() {
+            self.records()?
+        } else {
+            self.find_each(records)?
+        };
+        // A record given more than once is only converted once.
+        let mut unique: Vec<Record> = Vec::new();
+        for record in chosen {
+            if record.format != to && !unique.iter().any(|other| other.path == record.path) {
+                unique.push(record);
+            }
+        }
+        let chosen = unique;
+        if let Some(record) = chosen
+            .iter()
+            .find(|record| record.format == Format::AsciiDoc)
+        {
+            return Err(Error::InvalidInput(format!(
+                "{} is AsciiDoc, which can't be converted",
+                record.link_text()
+            )));
+        }
+
+        // Work out every new name first, so the links between the converted records can follow.
+        let mut targets: HashMap<String, String> = HashMap::new();
+        for record in &chosen {
+            let new_path = record.path.with_extension(to.extension());
+            if self.staging.exists(&new_path) {
+                return Err(Error::RecordExists(new_path.display().to_string()));
+            }
+            targets.insert(
+                record.relative_path(&self.config.record_path),
+                diff_paths(&new_path, &self.config.record_path)
+                    .unwrap_or(new_path)
+                    .display()
+                    .to_string(),
+            );
+        }
+
+        let mut converted: Vec<(String, Record)> = Vec::new();
+        for record in chosen {
+            let new_record = convert::record(&record, to, &targets);
+            self.staging.remove(&record.path);
+            self.save(&new_record);
+            converted.push((record.relative_path(&self.config.record_path), new_record));
+        }
+        for (old_target, record) in &converted {
+            self.rewrite_links(old_target, record)?;
+        }
+
+        if records.is_empty() {
+            self.change_file_type(to)?;
+        }
+        Ok(converted)
+    }
+
+    // Set the `fileType` in the config, so new records are written in this format, and write a
+    // copy of the template in that format next to the old one.
+    fn change_file_type(&self, to: Format) -> Result<(), Error> {
+        let Some(config_file) = &self.config.config_file else {
+            return Ok(());
+        };
+        let mut lines: Vec<String> = self
+            .staging
+            .read(config_file)?
+            .lines()
+            .map(String::from)
+            .collect();
+        let file_type = format!("fileType={}", to.extension());
+        match lines.iter_mut().find(|line| line.starts_with("fileType=")) {
+            Some(line) => *line = file_type,
+            None => lines.push(file_type),
+        }
+        let mut content = lines.join("\u{000A}");
+        content.push('\u{000A}');
+        self.staging.write(config_file, content);
+
+        if let Some(template) = &self.config.template_source {
+            let new_template = template.with_extension(to.extension());
+            if !self.staging.exists(&new_template) {
+                let record = Record::parse(template, &self.config.template_string, &self.config);
+                let record = convert::record(&record, to, &HashMap::new());
+                self.staging
+                    .write(&new_template, record.render(&self.config));
+            }
+        }
+        Ok(())
+    }
+
+    // Run an action which changes files, writing the files once it has worked (unless this is a
+    // dry run, where they're kept for `changes`), or forgetting them if it fails.
+    fn apply<T>(&self, action: impl FnOnce() -> Result<T, Error>) -> Result<T, Error> {
+        let earlier = self.staging.snapshot();
+        match action() {
+            Ok(result) => {
+                if !self.dry_run {
+                    self.staging.commit()?;
+                }
+                Ok(result)
+            }
+            Err(error) => {
+                self.staging.restore(earlier);
+                Err(error)
+            }
+        }
+    }
+
+    // Read a record as it is during the current action, finding the records its front matter links
+    // to among those as they are too.
+    fn load(&self, path: &Path) -> Result<Record, Error> {
+        let mut record = Record::parse(path, &self.staging.read(path)?, &self.config);
+        record.find_siblings(
+            &self.config,
+            |directory| self.staging.list_dir(directory),
+            |path| self.staging.read(path),
+        )?;
+        Ok(record)
+    }
+
+    fn save(&self, record: &Record) {
+        self.staging
+            .write(&record.path, record.render(&self.config));
+    }
+
+    // The files in the record directory, as they are during the current action.
+    fn record_files(&self) -> Result<Vec<PathBuf>, Error> {
+        Ok(self.staging.list_dir(&self.config.record_path)?)
+    }
+
+    // The line recording a change of the record to this status on the date of the change, e.g.
+    // "Approved on 2020-01-01", followed by who made the change, if the config asks for that
+    // (actor=git). The history is kept in the order it's written, so a change can't be dated
+    // before the record's latest one.
+    fn status_line(&self, record: &Record, status: &str) -> Result<String, Error> {
+        let date = self.date()?;
+        if let Some(last_date) = record.last_status_date() {
+            if date < last_date && !self.earlier_dates {
+                return Err(Error::InvalidInput(format!(
+                    "The date {} is before the last status change of {} ({}), so it can't be added to its history without --allow-earlier-date",
+                    date,
+                    record.link_text(),
+                    last_date
+                )));
+            }
+        }
+        // Translate the status, and replace the string DATE with the date of the change
+        let mut line = self.config.status_template(status).replace("DATE", &date);
+        if self.config.record_actor {
+            if let Some(actor) = git_user_name(&self.config.record_path) {
+                line.push(' ');
+                line.push_str(&self.config.translate("by %").replacen('%', &actor, 1));
+            }
+        }
+        Ok(line)
+    }
+
+    // Refuse to move the record to the status `to` if the workflow doesn't allow it.
+    fn check_transition(&self, record: &Record, to: &str) -> Result<(), Error> {
+        let from = record.status_name(&self.config);
+        if self.config.allows_transition(&from, to) {
+            return Ok(());
+        }
+        Err(Error::InvalidTransition {
+            record: record.link_text(),
+            from: from.clone(),
+            to: to.to_string(),
+            allowed: self
+                .config
+                .transitions
+                .get(&from)
+                .cloned()
+                .unwrap_or_default(),
+        })
+    }
+
+    // Point every relation which links to `old_target` at the record instead, with its current
+    // title, saving and returning the records which changed.
+    fn rewrite_links(&self, old_target: &str, record: &Record) -> Result<Vec<Record>, Error> {
+        let new_target = record.relative_path(&self.config.record_path);
+        let mut changed: Vec<Record> = Vec::new();
+
+        for mut other in self.records()? {
+            if other.path == record.path {
+                continue;
+            }
+            let mut modified = false;
+            for relation in &mut other.relations {
+                if relation.target == old_target {
+                    relation.target = new_target.clone();
+                    relation.title = record.link_text();
+                    modified = true;
+                }
+            }
+            if modified {
+                self.save(&other);
+                changed.push(other);
+            }
+        }
+        Ok(changed)
+    }
+
+    // Link each of the `from` records to the `to` record with the relation `kind`, and link the
+    // `to` record back to each of them with the reciprocal relation. When a record is superseded
+    // or deprecated, that is added to its status history. The changed records are returned, with
+    // the `to` record last, or none if every record was already related to it.
+    fn relate(
+        &self,
+        from: &[&str],
+        to: &str,
+        kind: RelationKind,
+        reason: Option<String>,
+    ) -> Result<Vec<Record>, Error> {
+        // Find every record first, so nothing is changed if any of them can't be found, or can't
+        // be superseded or deprecated from the status they're in.
+        let mut found = self.find_each(&[&[to], from].concat())?;
+        let mut record_to = found.remove(0);
+        let target = record_to.relative_path(&self.config.record_path);
+        // A record given twice is only changed once, and one which already has the relation is
+        // left as it is.
+        let mut records_from: Vec<Record> = Vec::new();
+        for record in found {
+            if record.path == record_to.path {
+                return Err(Error::InvalidInput(format!(
+                    "{} can't be related to itself",
+                    record.link_text()
+                )));
+            }
+            let related = record
+                .relations
+                .iter()
+                .any(|relation| relation.kind == kind && relation.target == target);
+            if !related && !records_from.iter().any(|other| other.path == record.path) {
+                records_from.push(record);
+            }
+        }
+        if records_from.is_empty() {
+            return Ok(Vec::new());
+        }
+        let new_status = match kind {
+            RelationKind::SupersededBy => Some("Superseded"),
+            RelationKind::DeprecatedBy => Some("Deprecated"),
+            _ => None,
+        };
+        if let Some(new_status) = new_status {
+            for record_from in &records_from {
+                self.check_transition(record_from, new_status)?;
+            }
+        }
+        let mut changed: Vec<Record> = Vec::new();
+
+        // Process the list of records to link from
+        for mut record_from in records_from {
+            let mut relation_to = Relation::new(kind, &record_to, &self.config.record_path);
+            relation_to.reason = reason.clone();
+            let mut relation_from =
+                Relation::new(kind.reciprocal(), &record_from, &self.config.record_path);
+            relation_from.reason = reason.clone();
+
+            // Update the "from" and "to" records with the respective links
+            if let Some(new_status) = new_status {
+                let line = self.status_line(&record_from, new_status)?;
+                record_from.add_status(line);
+            }
+            record_from.add_relation(relation_to);
+            self.save(&record_from);
+            record_to.add_relation(relation_from);
+            changed.push(record_from);
+        }
+        self.save(&record_to);
+        changed.push(record_to);
+        Ok(changed)
+    }
+}
+
+// Read a date given as YYYY-MM-DD.
+pub fn parse_date(date: &str) -> Result<NaiveDate, Error> {
+    NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+        .map_err(|_| Error::InvalidInput(format!("Invalid date \"{}\", expected YYYY-MM-DD", date)))
+}
+
+// Today's date, unless it's fixed by the DECISION_RECORD_DATE (as YYYY-MM-DD) or
+// SOURCE_DATE_EPOCH (as seconds since 1970) environment variables, so that builds and tests
+// produce the same records every time.
+pub fn today() -> Result<NaiveDate, Error> {
+    if let Ok(date) = env::var("DECISION_RECORD_DATE") {
+        return parse_date(&date);
+    }
+    if let Ok(epoch) = env::var("SOURCE_DATE_EPOCH") {
+        return epoch
+            .trim()
+            .parse::<i64>()
+            .ok()
+            .and_then(|seconds| DateTime::from_timestamp(seconds, 0))
+            .map(|date_time| date_time.date_naive())
+            .ok_or_else(|| {
+                Error::InvalidInput(format!("Invalid SOURCE_DATE_EPOCH \"{}\"", epoch))
+            });
+    }
+    Ok(Local::now().date_naive())
+}
+
+// The name of the person making the change, from `git config user.name`.
+fn git_user_name(directory: &Path) -> Option<String> {
+    Command::new("git")
+        .arg("config")
+        .arg("user.name")
+        .current_dir(directory)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+// When the file was added to the repository, as seconds since the epoch, from the git history if
+// it has been committed, or the time the file was last changed if not.
+fn added_time(path: &Path) -> i64 {
+    let directory = path.parent().unwrap_or_else(|| Path::new("."));
+    let filename = path.file_name().unwrap_or_default();
+    let from_git = Command::new("git")
+        .arg("log")
+        .arg("--diff-filter=A")
+        .arg("--format=%ct")
+        .arg("--")
+        .arg(filename)
+        .current_dir(directory)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .last()
+                .and_then(|line| line.trim().parse::<i64>().ok())
+        });
+    from_git.unwrap_or_else(|| {
+        metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs() as i64)
+            .unwrap_or(i64::MAX)
+    })
+}
+
+// Return the one record found when looking for `needle`, or say why there isn't exactly one.
+fn only_match(needle: &str, mut matches: Vec<Record>) -> Result<Record, Error> {
+    match matches.len() {
+        0 => Err(Error::RecordNotFound(needle.to_string())),
+        1 => Ok(matches.remove(0)),
+        _ => Err(Error::AmbiguousRecord {
+            record: needle.to_string(),
+            matches: matches
+                .iter()
+                .map(|candidate| candidate.link_text())
+                .collect(),
+        }),
+    }
+}
+
+// Find every file which starts with a (4 or more digit) number, and return them, with their
+// number, in numerical order. When the records aren't `numbered`, every record file (e.g.
+// `some-title.md`, or `2024-roadmap.md`) is returned in alphabetical order, with the number 0.
+fn list_records(files: &[PathBuf], numbered: bool) -> Vec<(i32, PathBuf)> {
+    let mut records: Vec<(i32, PathBuf)> = Vec::new();
+
+    for pathbuf_record in files.iter().cloned() {
+        let str_filename = match pathbuf_record.file_name() {
+            Some(filename) => filename.to_string_lossy().to_string(),
+            None => continue,
+        };
+        if let Some(captures) = RE_NUMBERED_FILE.captures(&str_filename) {
+            if !numbered {
+                records.push((0, pathbuf_record));
+            } else if let Ok(number) = captures[1].parse::<i32>() {
+                records.push((number, pathbuf_record));
+            }
+        } else if !numbered && RE_SLUG_FILE.is_match(&str_filename) {
+            records.push((0, pathbuf_record));
+        }
+    }
+    records.sort();
+    records
+}
@@ -0,0 +1,211 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fs::{create_dir, read_dir, read_to_string, remove_dir, remove_file, rename, File};
+use std::io::prelude::*;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+
+// A file which an action has written, created or removed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileChange {
+    pub path: PathBuf,
+    // The content on disk, or None if the file is being created.
+    pub before: Option<String>,
+    // The new content, or None if the file is being removed.
+    pub after: Option<String>,
+}
+
+// The files changed by an action, held in memory until the action has finished. Reads see the
+// changes made so far, so an action can work across several records before anything is written,
+// and a dry run can show what would have changed without touching the disk.
+#[derive(Debug, Default)]
+pub struct Staging {
+    // The new content of each changed file, or None for a removed file.
+    files: RefCell<BTreeMap<PathBuf, Option<String>>>,
+}
+
+impl Staging {
+    pub fn read(&self, path: &Path) -> Result<String, Error> {
+        match self.files.borrow().get(path) {
+            Some(Some(content)) => Ok(content.clone()),
+            Some(None) => Err(Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("{} has been removed", path.display()),
+            )),
+            None => read_to_string(path),
+        }
+    }
+
+    pub fn exists(&self, path: &Path) -> bool {
+        match self.files.borrow().get(path) {
+            Some(content) => content.is_some(),
+            None => path.exists(),
+        }
+    }
+
+    pub fn write(&self, path: &Path, content: String) {
+        self.files
+            .borrow_mut()
+            .insert(path.to_path_buf(), Some(content));
+    }
+
+    pub fn remove(&self, path: &Path) {
+        self.files.borrow_mut().insert(path.to_path_buf(), None);
+    }
+
+    // The files in a directory, including those created and leaving out those removed.
+    pub fn list_dir(&self, directory: &Path) -> Result<Vec<PathBuf>, Error> {
+        let mut paths: Vec<PathBuf> = Vec::new();
+        for entry in read_dir(directory)? {
+            paths.push(entry?.path());
+        }
+        for (path, content) in self.files.borrow().iter() {
+            if path.parent() == Some(directory) && content.is_some() && !paths.contains(path) {
+                paths.push(path.clone());
+            }
+        }
+        paths.retain(|path| self.exists(path));
+        Ok(paths)
+    }
+
+    // Every file which would be different on disk, compared with what's there now.
+    pub fn changes(&self) -> Result<Vec<FileChange>, Error> {
+        let mut changes: Vec<FileChange> = Vec::new();
+        for (path, after) in self.files.borrow().iter() {
+            let before = if path.exists() {
+                Some(read_to_string(path)?)
+            } else {
+                None
+            };
+            if before != *after {
+                changes.push(FileChange {
+                    path: path.clone(),
+                    before,
+                    after: after.clone(),
+                });
+            }
+        }
+        Ok(changes)
+    }
+
+    // Write the changes to disk, as close to all at once as we can: every new file is first written
+    // alongside the one it replaces (making any directories it needs), then they're all renamed
+    // into place. If anything fails part-way, the files already changed are put back as they
+    // were, and the directories made are removed again.
+    pub fn commit(&self) -> Result<(), Error> {
+        let files = std::mem::take(&mut *self.files.borrow_mut());
+
+        let mut originals: Vec<(PathBuf, Option<String>)> = Vec::new();
+        for path in files.keys() {
+            let original = if path.exists() {
+                Some(read_to_string(path)?)
+            } else {
+                None
+            };
+            originals.push((path.clone(), original));
+        }
+
+        let mut directories: Vec<PathBuf> = Vec::new();
+        let mut temporary_files: Vec<(PathBuf, PathBuf)> = Vec::new();
+        for (path, content) in &files {
+            if let Some(content) = content {
+                let temporary = temporary_path(path);
+                let written = create_parents(path, &mut directories)
+                    .and_then(|_| write_file(&temporary, content));
+                if let Err(error) = written {
+                    for (_, temporary) in &temporary_files {
+                        let _ = remove_file(temporary);
+                    }
+                    let _ = remove_file(&temporary);
+                    remove_directories(&directories);
+                    return Err(error);
+                }
+                temporary_files.push((path.clone(), temporary));
+            }
+        }
+
+        let mut done: Vec<PathBuf> = Vec::new();
+        let mut result: Result<(), Error> = Ok(());
+        for (path, content) in &files {
+            let step = match content {
+                Some(_) => temporary_files
+                    .iter()
+                    .find(|(target, _)| target == path)
+                    .map_or(Ok(()), |(_, temporary)| rename(temporary, path)),
+                None if path.exists() => remove_file(path),
+                None => Ok(()),
+            };
+            if let Err(error) = step {
+                result = Err(error);
+                break;
+            }
+            done.push(path.clone());
+        }
+
+        if result.is_err() {
+            for (_, temporary) in &temporary_files {
+                if temporary.exists() {
+                    let _ = remove_file(temporary);
+                }
+            }
+            for (path, original) in originals.iter().filter(|(path, _)| done.contains(path)) {
+                let _ = match original {
+                    Some(content) => write_file(path, content),
+                    None => remove_file(path),
+                };
+            }
+            remove_directories(&directories);
+        }
+        result
+    }
+
+    // The changes made so far, so they can be gone back to with `restore` if a later action fails.
+    pub fn snapshot(&self) -> BTreeMap<PathBuf, Option<String>> {
+        self.files.borrow().clone()
+    }
+
+    pub fn restore(&self, files: BTreeMap<PathBuf, Option<String>>) {
+        *self.files.borrow_mut() = files;
+    }
+}
+
+// A name for a file to be written next to `path` (so it's on the same filesystem and can be renamed
+// over it), which won't be mistaken for a record.
+fn temporary_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{}.{}.tmp", name, std::process::id()))
+}
+
+// Make the directories above `path` which don't exist yet, from the top down, adding each one made
+// to `directories`.
+fn create_parents(path: &Path, directories: &mut Vec<PathBuf>) -> Result<(), Error> {
+    let mut missing: Vec<&Path> = match path.parent() {
+        Some(parent) => parent
+            .ancestors()
+            .take_while(|directory| !directory.as_os_str().is_empty() && !directory.exists())
+            .collect(),
+        None => Vec::new(),
+    };
+    while let Some(directory) = missing.pop() {
+        create_dir(directory)?;
+        directories.push(directory.to_path_buf());
+    }
+    Ok(())
+}
+
+// Remove the directories made by `create_parents`, from the bottom up. A directory which isn't
+// empty is left alone.
+fn remove_directories(directories: &[PathBuf]) {
+    for directory in directories.iter().rev() {
+        let _ = remove_dir(directory);
+    }
+}
+
+fn write_file(path: &Path, content: &str) -> Result<(), Error> {
+    let mut file_object = File::create(path)?;
+    file_object.write_all(content.as_bytes())?;
+    file_object.sync_all()
+}